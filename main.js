@@ -8,4 +8,10 @@ mergeInto(LibraryManager.library, {
   end_game: function() {
   	return end_game();
   },
+  load_high_scores: function() {
+  	return load_high_scores();
+  },
+  save_high_scores: function() {
+  	return save_high_scores();
+  },
 });
@@ -0,0 +1,140 @@
+// checks every tile of a level and the levels that follow it, run from the top of the repository
+//   cargo run --bin validate-maps [resources/levels/level1.toml]
+use std::process::exit;
+
+// the game's own modules, built in again here. They can't use anything else from the crate,
+// level.rs only uses map_manifest.rs which comes in with it
+#[path = "../bus_tuning.rs"]
+mod bus_tuning;
+#[path = "../level.rs"]
+mod level;
+#[path = "../map_manifest.rs"]
+mod map_manifest;
+#[path = "../mission.rs"]
+#[allow(dead_code)]
+mod mission;
+#[path = "../tile_file.rs"]
+#[allow(dead_code)]
+mod tile_file;
+#[path = "../zombie_type.rs"]
+mod zombie_type;
+
+use bus_tuning::{BUS_TUNINGS_FILE, load_bus_tunings};
+use level::{DEFAULT_LEVEL, Level};
+use map_manifest::MapManifest;
+use mission::{load_missions, Mission, MISSIONS_FILE, Objective};
+use tile_file::{check_tile, parse_tile};
+use zombie_type::{load_zombie_types, ZOMBIE_TYPES_FILE};
+
+fn main() {
+    let mut next = Some(std::env::args().nth(1).unwrap_or(String::from(DEFAULT_LEVEL)));
+    let mut checked: Vec<MapManifest> = vec![];
+    let mut visited: Vec<String> = vec![];
+    let mut problems = 0;
+    let zombie_types = load_zombie_types(ZOMBIE_TYPES_FILE).unwrap_or_else(|e| {
+        println!("{}", e);
+        exit(1);
+    });
+    let bus_tunings = load_bus_tunings(BUS_TUNINGS_FILE).unwrap_or_else(|e| {
+        println!("{}", e);
+        exit(1);
+    });
+    while let Some(filename) = next {
+        // levels are allowed to chain back round to an earlier one
+        if visited.contains(&filename) {
+            break;
+        }
+        let level = Level::load(&filename).unwrap_or_else(|e| {
+            println!("{}", e);
+            exit(1);
+        });
+        println!("{} \"{}\"", filename, level.name);
+        for name in level.zombie_weights.keys() {
+            if !zombie_types.iter().any(|z| &z.name == name) {
+                println!("{}: no zombie called {} in {}", filename, name, ZOMBIE_TYPES_FILE);
+                problems = problems + 1;
+            }
+        }
+        if !bus_tunings.iter().any(|b| b.name == level.bus) {
+            println!("{}: no bus called {} in {}", filename, level.bus, BUS_TUNINGS_FILE);
+            problems = problems + 1;
+        }
+        if !checked.contains(&level.map) {
+            problems = problems + check_map(&level.map);
+            checked.push(level.map.clone());
+        }
+        visited.push(filename);
+        next = level.next;
+    }
+    problems = problems + check_missions();
+    if problems > 0 {
+        println!("{} problems found", problems);
+        exit(1);
+    }
+}
+
+/// Every mission's level has to load, it isn't followed on to the levels after it.
+fn check_missions() -> usize {
+    let missions = match load_missions(MISSIONS_FILE) {
+        Ok(missions) => missions,
+        Err(e) => {
+            println!("{}", e);
+            return 1;
+        }
+    };
+    let mut problems = 0;
+    for mission in missions.iter() {
+        match Level::load(&mission.level) {
+            Ok(level) => {
+                if let Some(problem) = check_mission(mission, &level) {
+                    println!("{}: {}", MISSIONS_FILE, problem);
+                    problems = problems + 1;
+                }
+            }
+            Err(e) => {
+                println!("{}: mission {}: {}", MISSIONS_FILE, mission.name, e);
+                problems = problems + 1;
+            }
+        }
+    }
+    if problems == 0 {
+        println!("{} ok, {} missions", MISSIONS_FILE, missions.len());
+    }
+    problems
+}
+
+/// Problems with a mission that only show once its level is loaded.
+fn check_mission(mission: &Mission, level: &Level) -> Option<String> {
+    if let Objective::REACH { tile } = mission.objective {
+        if tile[0] >= level.map.size || tile[1] >= level.map.size {
+            return Some(format!("{}: tile {}_{} is off the {} by {} map of {}", mission.name, tile[0], tile[1], level.map.size, level.map.size, mission.level));
+        }
+    }
+    None
+}
+
+fn check_map(map_manifest: &MapManifest) -> usize {
+    let mut problems = 0;
+    for xx in 0..map_manifest.size {
+        for zz in 0..map_manifest.size {
+            let filename = map_manifest.tile_filename(xx, zz);
+            match parse_tile(&filename) {
+                Ok(tile) => {
+                    let found = check_tile(&tile);
+                    for problem in found.iter() {
+                        println!("{}", problem);
+                    }
+                    if found.is_empty() {
+                        println!("{} ok, {} polygons {} objects", filename, tile.polygons.len(), tile.objects.len());
+                    }
+                    problems = problems + found.len();
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    problems = problems + 1;
+                }
+            }
+        }
+    }
+    problems
+}
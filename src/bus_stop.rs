@@ -0,0 +1,51 @@
+use cgmath::{InnerSpace, vec3, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::road_graph::RoadGraph;
+
+// how close the bus has to be and how slow it has to be going, in world units per second, for people to get on
+const STOP_RADIUS: f32 = 0.6;
+pub const PICKUP_SPEED: f32 = 0.5;
+// one passenger gets on this often while the bus waits
+const BOARD_SECONDS: f32 = 0.5;
+pub const MAX_QUEUE: usize = 5;
+const QUEUE_SPACING: f32 = 0.12;
+
+/// An o,stop,x,y,z,name record, where humans wait to be picked up.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BusStop {
+    pub name: String,
+    pub position: Vector3<f32>,
+    boarding_timer: f32,
+}
+
+impl BusStop {
+    pub fn new(name: String, position: Vector3<f32>) -> BusStop {
+        BusStop {
+            name,
+            position,
+            boarding_timer: 0.0,
+        }
+    }
+
+    /// Where the person in place 0, 1, 2... of the queue stands.
+    pub fn queue_position(&self, place: usize) -> Vector3<f32> {
+        self.position + vec3(place as f32 * QUEUE_SPACING, 0.0, 0.0)
+    }
+
+    /// True when the next one in the queue can get on, the bus has to have waited BOARD_SECONDS.
+    pub fn ready_to_board(&mut self, delta: f32, bus_position: Vector3<f32>, bus_speed: f32, roads: &RoadGraph) -> bool {
+        let mut to_bus = roads.wrapped_delta(self.position, bus_position);
+        to_bus.y = 0.0;
+        if bus_speed >= PICKUP_SPEED || to_bus.magnitude() > STOP_RADIUS {
+            self.boarding_timer = 0.0;
+            return false;
+        }
+        self.boarding_timer += delta;
+        if self.boarding_timer >= BOARD_SECONDS {
+            self.boarding_timer = 0.0;
+            return true;
+        }
+        false
+    }
+}
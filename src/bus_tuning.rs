@@ -0,0 +1,55 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+pub const BUS_TUNINGS_FILE: &str = "resources/buses.toml";
+
+/// How one bus drives, one [[bus]] table in buses.toml. Distances are world units,
+/// forces over mass give world units per second per second.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct BusTuning {
+    /// what levels use to pick the bus
+    pub name: String,
+    pub mass: f32,
+    /// front to back axle, a longer bus turns wider
+    pub wheelbase: f32,
+    pub engine_force: f32,
+    /// engine force going backwards
+    pub reverse_force: f32,
+    pub brake_force: f32,
+    /// most sideways acceleration the tyres hold, steering is cut back at speed to stay under it
+    pub grip: f32,
+    /// degrees the front wheels turn at full lock
+    pub max_steer: f32,
+    /// degrees per second the wheels turn towards where they are steered, and back to the middle
+    pub steer_speed: f32,
+    /// air resistance, goes up with the square of the speed
+    pub drag: f32,
+    /// tyres on the road, goes up with the speed
+    pub rolling_resistance: f32,
+}
+
+#[derive(Deserialize)]
+struct BusTuningsFile {
+    bus: Vec<BusTuning>,
+}
+
+pub fn load_bus_tunings(filename: &str) -> Result<Vec<BusTuning>, String> {
+    let contents = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let file: BusTuningsFile = toml::from_str(&contents).map_err(|e| format!("{}: {}", filename, e))?;
+    if file.bus.is_empty() {
+        return Err(format!("{}: no buses", filename));
+    }
+    for bus in file.bus.iter() {
+        if bus.mass <= 0.0 || bus.wheelbase <= 0.0 || bus.grip <= 0.0 {
+            return Err(format!("{}: {} needs mass, wheelbase and grip above 0.0", filename, bus.name));
+        }
+        if bus.max_steer <= 0.0 || bus.max_steer >= 90.0 {
+            return Err(format!("{}: {} max_steer should be between 0 and 90 degrees", filename, bus.name));
+        }
+        if file.bus.iter().filter(|b| b.name == bus.name).count() > 1 {
+            return Err(format!("{}: {} is there more than once", filename, bus.name));
+        }
+    }
+    Ok(file.bus)
+}
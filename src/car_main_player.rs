@@ -1,24 +1,22 @@
 
-use cgmath::{Angle, Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, Transform, vec3, Vector3, Zero, MetricSpace};
-
-use crate::{gl, point2vec, get_start_time, output_elapsed};
-use crate::flying_camera::Flying_Camera;
-use crate::game::MovementAndCollision;
-use crate::gl_helper::instance_model::ModelInstance;
-use crate::gl_helper::model::Model;
-use crate::ground::{BY, Ground};
+use cgmath::{Angle, Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, Transform, vec3, Vector3, Zero};
+use serde::{Deserialize, Serialize};
+
+use crate::{point2vec, get_start_time, output_elapsed};
+use crate::bus_tuning::BusTuning;
+use crate::ground::Ground;
 //use std::ops::{AddAssign, Add, Mul};
-use crate::landscape::{LandscapeObject, SQUARE_COLUMNS, SQUARE_SIZE};
-use crate::sound::{ play, WARNING};
-//use crate::gl_helper::texture::create_texture;
+use crate::landscape::LandscapeObject;
 //use std::ops::AddAssign;
-use crate::scenery::Scenery;
+use crate::scenery::{Scenery, SceneryType};
+use crate::world::MovementAndCollision;
 
+#[derive(Serialize, Deserialize, Clone)]
 pub struct CarMainPlayer {
-    pub(crate) model_instances: Vec<ModelInstance>,
     pub(crate) movement_collision: MovementAndCollision,
-    matrix: Matrix4<f32>,
+    pub(crate) matrix: Matrix4<f32>,
     rotation_y_axis: Matrix4<f32>,
+    /// degrees the front wheels are turned, positive is to the right
     steering: f32,
     angle:f32,
     pub rotation_y: f32,
@@ -26,42 +24,62 @@ pub struct CarMainPlayer {
     pub rotation_x: f32,
     force: Matrix4<f32>,
     ahead_force: Matrix4<f32>,
-    pub accelerator_pressed: f32,
+    /// world units per second along the way the bus faces, below 0.0 is backwards
+    pub speed: f32,
+    /// 0.0 to 1.0
+    throttle: f32,
+    /// 0.0 to 1.0
+    brake: f32,
+    /// -1.0 full left to 1.0 full right
+    steer: f32,
+    /// the engine pushes backwards, going into reverse while still moving forward slows the bus first
+    reverse: bool,
+    pub tuning: BusTuning,
     applied_rotation: Matrix4<f32>,
     gravity: f32,
-    forward_reverse: f32,
+    gravity_timer: f32,
     dir: Vector3<f32>,
     pub off_road:f32,
+    pub off_road_warning: bool,
     pub msg: String,
-    tick:f32,
+    pub(crate) tick:f32,
+    start_position: Vector3<f32>,
+    start_heading: f32,
 }
 
 const MODEL_HEIGHT: f32 = 0.007;
 const GRAVITY_ADD: f32 = 0.05;
 const GRAVITY_MAX: f32 = 0.05;
-const SCALE: f32 = 0.013;
+const GRAVITY_SECONDS: f32 = 1.5;
+// off_road goes over 40 after about a second off the road
+const OFF_ROAD_PER_SECOND: f32 = 40.0;
+pub const SCALE: f32 = 0.013;
 const GRAVITY: bool = true;
+// slower than this with nothing pushing it counts as stopped
+const STOPPED_SPEED: f32 = 0.02;
+// how fast the bus texture animates for each world unit travelled
+const TICKS_PER_UNIT: f32 = 5.0;
+// size of the bus looking down from above when running into scenery
+const BUS_RADIUS: f32 = 0.15;
+// glancing blows and scraping along a wall slower than this aren't reported
+pub const CRASH_SPEED: f32 = 0.3;
 
-fn start_position() -> Vector3<f32> {
-    vec3(0.0, 2.0, 0.0)
+/// The bus running into scenery, impact_speed is the part of its speed that went into it.
+#[derive(Clone, Copy, Debug)]
+pub struct SceneryHit {
+    pub scenery_type: SceneryType,
+    pub impact_speed: f32,
 }
 
 impl CarMainPlayer {
-    pub fn new(gl: &gl::Gl) -> CarMainPlayer {
+    /// heading is in degrees about y, where the level says the bus starts and restarts after going off road
+    pub fn new(start_position: Vector3<f32>, start_heading: f32, tuning: BusTuning) -> CarMainPlayer {
         let start = get_start_time();
 
-        let mut model_instances:Vec<ModelInstance> = vec![];
-        let model = Model::new(gl, "resources/models/bus.obj", "resources/models/bus.png");
-        model_instances.push( ModelInstance::new(gl, model.clone(), SCALE, Some("resources/models/bus1.png" ) ) );
-        model_instances.push( ModelInstance::new(gl, model.clone(), SCALE, Some("resources/models/bus2.png" ) ) );
-        model_instances.push( ModelInstance::new(gl, model.clone(), SCALE, Some("resources/models/bus3.png" ) ) );
-        model_instances.push( ModelInstance::new(gl, model.clone(), SCALE, Some("resources/models/bus4.png" ) ) );
-
         output_elapsed(start,"time elapsed for car_main_player new()");
         CarMainPlayer {
-            model_instances,
-            movement_collision: MovementAndCollision::new(MODEL_HEIGHT * 1.25, start_position()),
-            matrix: Matrix4::from_translation(start_position()),
+            movement_collision: MovementAndCollision::new(BUS_RADIUS, start_position),
+            matrix: Matrix4::from_translation(start_position),
             rotation_y_axis: Matrix4::from_angle_y(Deg(0.0)),
             rotation_y: 0.0,
             rotation_x_axis: Matrix4::from_angle_x(Deg(0.0)),
@@ -70,25 +88,34 @@ impl CarMainPlayer {
             rotation_x: 0.0,
             force: Matrix4::from_translation(vec3(0.0, 0.0, 0.0)),
             ahead_force: Matrix4::from_translation(vec3(0.0, 0.0, 0.0)),
-            accelerator_pressed: 0.0,
-            applied_rotation: Matrix4::from_translation(vec3(0.0, 0.0, 0.0)),
-            forward_reverse: -1.0,
+            speed: 0.0,
+            throttle: 0.0,
+            brake: 0.0,
+            steer: 0.0,
+            reverse: false,
+            tuning,
+            applied_rotation: Matrix4::from_angle_y(Deg(start_heading)),
             dir: vec3(0.0, 0.00, 0.0),
             gravity: GRAVITY_ADD,
+            gravity_timer: 0.0,
             off_road:0.0,
+            off_road_warning: false,
             msg: "".to_string(),
             tick:0.0,
+            start_position,
+            start_heading,
         }
     }
 
     pub fn reset(&mut self) {
         //stop(ENGINE);
-        self.movement_collision.position = start_position();
-        self.applied_rotation = Matrix4::from_translation(vec3(0.0, 0.0, 0.0));
+        self.movement_collision.position = self.start_position;
+        self.applied_rotation = Matrix4::from_angle_y(Deg(self.start_heading));
         self.force = Matrix4::from_translation(vec3(0.0, 0.0, 0.0));
         self.gravity = GRAVITY_ADD;
         self.dir = Vector3::<f32>::zero();
-        self.accelerator_pressed = 0.0;
+        self.speed = 0.0;
+        self.steering = 0.0;
         self.off_road = 0.0;
     }
     pub fn off_road_too_much(&mut self) -> bool {
@@ -99,41 +126,66 @@ impl CarMainPlayer {
         }
         false
     }
-    pub fn go_forward(&mut self, change_by: f32, _ground: &Ground) {
-        if self.accelerator_pressed == 0.0 {
-            self.forward_reverse = -change_by;
-        }
+    pub fn select_reverse(&mut self, reverse: bool) {
+        self.reverse = reverse;
     }
 
+    /// What the driver is doing with the pedals and wheel until it is next called.
+    pub fn drive(&mut self, throttle: f32, brake: f32, steer: f32) {
+        self.throttle = throttle.clamp(0.0, 1.0);
+        self.brake = brake.clamp(0.0, 1.0);
+        self.steer = steer.clamp(-1.0, 1.0);
+    }
 
+    /// Bicycle model, the front wheels steer and the bus turns about the back axle.
+    fn update_speed_and_steering(&mut self, delta: f32) {
+        let tuning = &self.tuning;
 
-    pub fn steer_rotation_y_constant(&mut self, change_by: f32) {
-        let max =0.75;
-        self.steering = self.steering + change_by;
-        if self.steering > max { self.steering = max }
-        if self.steering < -max { self.steering = -max }
-    }
-    pub fn accelerate(&mut self, forward_by: f32, _ground: &Ground) {
-        self.accelerator_pressed = self.accelerator_pressed + forward_by;
-        if self.accelerator_pressed < 0.0 {
-            self.accelerator_pressed = 0.0;
+        // the tyres only hold so much sideways, speed squared over the turn radius, so less lock at speed
+        let mut steer_to = self.steer * tuning.max_steer;
+        let speed_squared = self.speed * self.speed;
+        if speed_squared > 0.0 {
+            let grip_limit = Deg::atan(tuning.grip * tuning.wheelbase / speed_squared).0;
+            steer_to = steer_to.max(-grip_limit).min(grip_limit);
+        }
+        let steer_by = tuning.steer_speed * delta;
+        if self.steering < steer_to {
+            self.steering = (self.steering + steer_by).min(steer_to);
+        } else {
+            self.steering = (self.steering - steer_by).max(steer_to);
         }
-        if self.accelerator_pressed > 0.5 {
-            self.accelerator_pressed = 0.5;
+
+        let push = if self.reverse { -self.throttle * tuning.reverse_force } else { self.throttle * tuning.engine_force };
+        let resist = tuning.drag * self.speed * self.speed.abs() + tuning.rolling_resistance * self.speed;
+        let mut speed = self.speed + (push - resist) / tuning.mass * delta;
+        // brakes bring the bus to a stop, they never push it the other way
+        let brake_by = self.brake * tuning.brake_force / tuning.mass * delta;
+        if speed > 0.0 {
+            speed = (speed - brake_by).max(0.0);
+        } else {
+            speed = (speed + brake_by).min(0.0);
         }
+        if self.throttle == 0.0 && speed.abs() < STOPPED_SPEED {
+            speed = 0.0;
+        }
+        self.speed = speed;
     }
-    pub fn update(&mut self, delta: f32, ground: &Ground, _camera: &Flying_Camera, tick: i128, ) {
-        if self.accelerator_pressed > 0.01 {
+
+    pub fn update(&mut self, delta: f32, ground: &Ground) -> Option<SceneryHit> {
+        self.update_speed_and_steering(delta);
+        if self.speed != 0.0 {
+            let travelled = self.speed.abs() * delta * TICKS_PER_UNIT;
             if self.angle > 0.0 && self.angle < 180.0 {
-                self.tick = self.tick + self.accelerator_pressed ;
+                self.tick += travelled;
             } else {
-                self.tick = self.tick - self.accelerator_pressed ;
+                self.tick -= travelled;
             }
             if self.tick < 0.0 {
                 self.tick = 100000.0;
             }
-            self.rotation_y_axis = Matrix4::from_angle_y(Deg(-self.steering));
-            self.angle = self.angle +self.steering;
+            let turn = (self.speed * Deg(self.steering).tan() / self.tuning.wheelbase * delta).to_degrees();
+            self.rotation_y_axis = Matrix4::from_angle_y(Deg(-turn));
+            self.angle += turn;
             if self.angle >= 360.0 {
                 self.angle = self.angle - 360.0;
             }
@@ -143,8 +195,8 @@ impl CarMainPlayer {
         } else {
             self.rotation_y_axis = Matrix4::from_angle_y(Deg(0.0));
         }
-        let mut dir = vec3(0.0, 0.0, self.accelerator_pressed * self.forward_reverse);
-        dir = self.applied_rotation.transform_vector(dir) * 0.1;
+        let mut dir = vec3(0.0, 0.0, -self.speed * delta);
+        dir = self.applied_rotation.transform_vector(dir);
         self.dir = dir ; //self.dir + dir;
 
         self.applied_rotation = self.applied_rotation * self.rotation_x_axis * self.rotation_y_axis;
@@ -166,7 +218,9 @@ impl CarMainPlayer {
         if GRAVITY {
             self.matrix.w.y = self.matrix.w.y - self.gravity;
         }
-        if tick % 60 <= 1 {
+        self.gravity_timer += delta;
+        if self.gravity_timer >= GRAVITY_SECONDS {
+            self.gravity_timer = 0.0;
             //self.dir = self.dir * 0.9;
             if self.gravity <= GRAVITY_MAX {
                 self.gravity = self.gravity + GRAVITY_ADD;
@@ -174,26 +228,26 @@ impl CarMainPlayer {
         }
         self.update_position();
 
-        let half_width = (SQUARE_COLUMNS / 2) as f32 * SQUARE_SIZE * BY as f32;
+        let half_width = ground.width() / 2.0;
 
         //println!("{} {} {} {}",self.thrust.w.x,self.thrust.w.y,self.thrust.w.z,self.thrust.w.w);
 
         if self.movement_collision.position.x < -half_width {
             println!("b4 x< 0 Reset x={},z={} {}", self.movement_collision.position.x, self.movement_collision.position.z, self.force.w.y);
-            self.flip_reset_the_matrix(1.0, 0.0);
+            self.flip_reset_the_matrix(1.0, 0.0, ground.width());
             println!("x< 0 Reset x={},z={}", self.movement_collision.position.x, self.movement_collision.position.z);
         } else if self.movement_collision.position.x > half_width {
             println!("b4 x> Reset x={},z={}  {}", self.movement_collision.position.x, self.movement_collision.position.z, self.force.w.y);
-            self.flip_reset_the_matrix(-1.0, 0.0);
+            self.flip_reset_the_matrix(-1.0, 0.0, ground.width());
             println!("x> Reset x={},z={}", self.movement_collision.position.x, self.movement_collision.position.z);
         }
         if self.movement_collision.position.z <= -half_width {
             println!("b4 z<0 Reset x={},z={}  {}", self.movement_collision.position.x, self.movement_collision.position.z, self.force.w.y);
-            self.flip_reset_the_matrix(0.0, 1.0);
+            self.flip_reset_the_matrix(0.0, 1.0, ground.width());
             println!("z<0 Reset x={},z={}", self.movement_collision.position.x, self.movement_collision.position.z);
         } else if self.movement_collision.position.z >= half_width {
             println!("b4 z> Reset x={},z={}  {}", self.movement_collision.position.x, self.movement_collision.position.z, self.force.w.y);
-            self.flip_reset_the_matrix(0.0, -1.0);
+            self.flip_reset_the_matrix(0.0, -1.0, ground.width());
         }
         let ahead_matrix = self.matrix * self.rotation_y_axis * self.rotation_x_axis * self.ahead_force;
         let ahead = CarMainPlayer::position_ahead(ahead_matrix);
@@ -207,31 +261,26 @@ impl CarMainPlayer {
         if self.off_road > 0.0 {
             self.off_road = self.off_road - delta * 5.0;
         }
-        if over.is_none() {
-            play(WARNING);
-        }
+        self.off_road_warning = over.is_none();
         self.msg = String::new();
-        self.off_road = self.off_road + over.map_or(1.0 , |l:&LandscapeObject| {
+        self.off_road += OFF_ROAD_PER_SECOND * delta * over.map_or(1.0 , |l:&LandscapeObject| {
             if l.description.contains("road") {
                 self.msg = l.description.clone();
                 0.0
             } else {
-                play(WARNING);
+                self.off_road_warning = true;
                 1.0
             }
         });
-        let (hit_scenery,xyz) = ground.scenery_at(self.movement_collision.position.x, self.movement_collision.position.z);
-        hit_scenery.map(|l:&Scenery| {
-            //let distance = l.movement_collision.position.distance(self.movement_collision.position);
-            let distance = (xyz + l.position).distance(self.movement_collision.position);
-            if distance < l.movement_collision.radius {
-                self.msg = format!("HIT!!!!!!! Over  {:?} {} {}",l.scenery_type, l.position.x,l.position.z);
-                println!("**** HIT!!!!!!! Over  {} {:?} {} {}",distance,l.scenery_type, l.position.x,l.position.z);
-                self.matrix = original_matrix * self.rotation_y_axis * self.rotation_x_axis;
-                self.update_position();
+        let mut hit: Option<SceneryHit> = None;
+        for (scenery, tile_xyz) in ground.scenery_within(self.movement_collision.position, self.movement_collision.radius) {
+            if let Some(this_hit) = self.bounce_off(scenery, tile_xyz) {
+                // report whichever took the most speed off the bus
+                if hit.is_none_or(|h| this_hit.impact_speed > h.impact_speed) {
+                    hit = Some(this_hit);
+                }
             }
-            println!("HIT!!!!!!! Over  {} {:?} {} {}",distance,l.scenery_type, l.position.x,l.position.z);
-        });
+        }
 
 
         let ground_height = ground.position_height(self.movement_collision.position.x, self.movement_collision.position.z);
@@ -251,23 +300,39 @@ impl CarMainPlayer {
             println!("ROLLBACK height {} ", ground_height);
             //self.crashed(special_effects);
         }
+        hit
+    }
 
-
-        if self.accelerator_pressed > 0.0 && tick % 3 == 0 {
-            let mut dir = vec3(0.0, MODEL_HEIGHT, 0.0);
-            dir = self.applied_rotation.transform_vector(dir);
-            /*
-            for _i in 1..rng.gen_range(2, 5) {
-                let pos = self.movement_collision.position - dir * rng.gen_range(0.95, 1.75);
-                special_effects.thrust(pos, self.dir, delta);
-            }
-             */
+    /// Moves the bus back out of the scenery so it slides along the wall or round the tree,
+    /// losing the part of its speed that was going into it, all of it when hit head on.
+    fn bounce_off(&mut self, scenery: &Scenery, tile_xyz: Vector3<f32>) -> Option<SceneryHit> {
+        let push = scenery.push_out(tile_xyz, self.movement_collision.position, self.movement_collision.radius)?;
+        let impact_speed = self.bump(push);
+        if impact_speed < CRASH_SPEED {
+            return None;
         }
+        Some(SceneryHit { scenery_type: scenery.scenery_type, impact_speed })
     }
 
-    fn flip_reset_the_matrix(&mut self, x: f32, z: f32) {
-        let width = (SQUARE_COLUMNS) as f32 * SQUARE_SIZE * BY as f32;
+    /// Pushed by push out of something, losing the part of its speed that went into it.
+    /// Returns that part, 0.0 if the bus was moving away.
+    pub fn bump(&mut self, push: Vector3<f32>) -> f32 {
+        self.matrix.w.x += push.x;
+        self.matrix.w.z += push.z;
+        self.update_position();
+
+        let moving = self.applied_rotation.transform_vector(vec3(0.0, 0.0, -self.speed.signum()));
+        let into = -moving.dot(push.normalize());
+        if into <= 0.0 {
+            return 0.0;
+        }
+        let impact_speed = self.speed.abs() * into;
+        self.speed *= 1.0 - into;
+        impact_speed
+    }
 
+    fn flip_reset_the_matrix(&mut self, x: f32, z: f32, width: f32) {
+        
         if !x.is_zero() {
             self.matrix.w.x = self.matrix.w.x + (x * width);
         }
@@ -289,14 +354,157 @@ impl CarMainPlayer {
         point = matrix.transform_point(point);
         point2vec(point)
     }
-    pub(crate) fn render(&mut self, gl: &gl::Gl, view: &Matrix4<f32>, projection: &Matrix4<f32>, our_shader: u32) {
-        let alt = if self.tick% 2.0 < 1.25 {
-            true
-        } else {
-            false
-        };
-        let instance = (self.tick % self.model_instances.len() as f32) as usize ;
-        self.model_instances[instance].matrix = self.matrix;
-        self.model_instances[instance].render(gl, &view, &projection, our_shader, alt);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bus_tuning::{BUS_TUNINGS_FILE, load_bus_tunings};
+
+    use super::*;
+
+    const STEP: f32 = 1.0 / 40.0;
+
+    fn bus(name: &str) -> CarMainPlayer {
+        let tuning = load_bus_tunings(BUS_TUNINGS_FILE).unwrap().into_iter().find(|t| t.name == name).unwrap();
+        CarMainPlayer::new(vec3(0.0, 0.0, 0.0), 0.0, tuning)
     }
-}
\ No newline at end of file
+
+    /// How far the bus goes in that long.
+    fn run(bus: &mut CarMainPlayer, seconds: f32) -> f32 {
+        let mut travelled = 0.0;
+        for _ in 0..(seconds / STEP) as usize {
+            bus.update_speed_and_steering(STEP);
+            travelled += bus.speed.abs() * STEP;
+        }
+        travelled
+    }
+
+    fn stopping_distance(name: &str, from_speed: f32) -> f32 {
+        let mut bus = bus(name);
+        bus.speed = from_speed;
+        bus.drive(0.0, 1.0, 0.0);
+        let distance = run(&mut bus, 10.0);
+        assert_eq!(bus.speed, 0.0);
+        distance
+    }
+
+    #[test]
+    fn braking_takes_a_distance_to_stop() {
+        let tuning = bus("double decker").tuning;
+        let distance = stopping_distance("double decker", 2.0);
+        // the brakes alone would take speed squared * mass / (2 * brake force), drag helps a bit
+        let brakes_only = 2.0 * 2.0 * tuning.mass / (2.0 * tuning.brake_force);
+        assert!(distance < brakes_only && distance > brakes_only * 0.6, "{} against {}", distance, brakes_only);
+        assert!(stopping_distance("double decker", 3.0) > distance * 1.5);
+    }
+
+    #[test]
+    fn the_brakes_never_push_backwards() {
+        let mut bus = bus("minibus");
+        bus.speed = 0.5;
+        bus.drive(0.0, 1.0, 0.0);
+        run(&mut bus, 2.0);
+        assert_eq!(bus.speed, 0.0);
+        bus.select_reverse(true);
+        bus.drive(1.0, 0.0, 0.0);
+        run(&mut bus, 1.0);
+        assert!(bus.speed < 0.0);
+    }
+
+    #[test]
+    fn less_steering_at_speed() {
+        let mut slow = bus("double decker");
+        slow.speed = 0.5;
+        slow.drive(0.0, 0.0, 1.0);
+        // rolling along, nothing pushing or braking, for long enough to get to full lock
+        run(&mut slow, 0.5);
+        assert!((slow.steering - slow.tuning.max_steer).abs() < 0.01);
+
+        let mut fast = bus("double decker");
+        fast.speed = 3.0;
+        fast.drive(1.0, 0.0, 1.0);
+        run(&mut fast, 0.5);
+        assert!(fast.steering < slow.steering / 2.0);
+        // turning no tighter than the tyres can hold
+        let sideways = fast.speed * fast.speed * Deg(fast.steering).tan() / fast.tuning.wheelbase;
+        assert!(sideways <= fast.tuning.grip * 1.01, "{}", sideways);
+
+        fast.drive(1.0, 0.0, -1.0);
+        run(&mut fast, 0.5);
+        assert!(fast.steering < 0.0);
+    }
+
+    #[test]
+    fn tunings_drive_differently() {
+        let mut double_decker = bus("double decker");
+        let mut minibus = bus("minibus");
+        double_decker.drive(1.0, 0.0, 0.0);
+        minibus.drive(1.0, 0.0, 0.0);
+        // the minibus gets away quicker
+        assert!(run(&mut minibus, 1.0) > run(&mut double_decker, 1.0) * 1.1);
+        // and stops sooner
+        assert!(stopping_distance("minibus", 2.0) < stopping_distance("double decker", 2.0) * 0.9);
+
+        double_decker.drive(0.0, 0.0, 1.0);
+        minibus.drive(0.0, 0.0, 1.0);
+        double_decker.speed = 0.5;
+        minibus.speed = 0.5;
+        run(&mut double_decker, 1.0);
+        run(&mut minibus, 1.0);
+        assert!(minibus.steering > double_decker.steering);
+    }
+
+    fn heading_north_at(degrees: f32, speed: f32) -> CarMainPlayer {
+        let mut bus = bus("double decker");
+        bus.applied_rotation = Matrix4::from_angle_y(Deg(degrees));
+        bus.speed = speed;
+        bus
+    }
+
+    #[test]
+    fn head_on_into_a_wall_stops_the_bus() {
+        let mut bus = heading_north_at(0.0, 2.0);
+        // the wall to the north pushes the bus south
+        let impact = bus.bump(vec3(0.0, 0.0, 0.05));
+        assert!((impact - 2.0).abs() < 0.0001);
+        assert!(bus.speed.abs() < 0.0001);
+    }
+
+    #[test]
+    fn at_an_angle_the_bus_slides_along_the_wall() {
+        let house = Scenery::new_house(vec3(0.0, 0.0, -1.0));
+        // 60 degrees off heading straight at it, into the south wall of the house
+        let mut bus = heading_north_at(60.0, 2.0);
+        bus.matrix = Matrix4::from_translation(vec3(0.1, 0.0, -0.5));
+        bus.update_position();
+        let push = house.push_out(Vector3::zero(), bus.movement_collision.position, BUS_RADIUS).unwrap();
+        let impact = bus.bump(push);
+        // moved straight out from the wall and no further along it
+        assert!((bus.movement_collision.position.x - 0.1).abs() < 0.0001);
+        assert!((bus.movement_collision.position.z - (-1.0 + 0.36 + BUS_RADIUS)).abs() < 0.0001);
+        // cos 60 of the speed went into the wall, the rest carries on
+        assert!((impact - 1.0).abs() < 0.0001);
+        assert!((bus.speed - 1.0).abs() < 0.0001);
+
+        // a more glancing blow keeps more of it
+        let mut glancing = heading_north_at(80.0, 2.0);
+        let impact = glancing.bump(vec3(0.0, 0.0, 0.05));
+        assert!(impact < 0.5 && glancing.speed > 1.5);
+    }
+
+    #[test]
+    fn backing_away_from_a_tree_is_no_hit() {
+        let tree = Scenery::new_tree(vec3(0.0, 0.0, 0.0));
+        // the tree is to the south and the bus going north
+        let mut bus = heading_north_at(0.0, 1.0);
+        bus.matrix = Matrix4::from_translation(vec3(0.0, 0.0, -0.25));
+        bus.update_position();
+        let push = tree.push_out(Vector3::zero(), bus.movement_collision.position, BUS_RADIUS).unwrap();
+        assert_eq!(bus.bump(push), 0.0);
+        assert_eq!(bus.speed, 1.0);
+        // reversing into it is
+        let mut bus = heading_north_at(0.0, -1.0);
+        assert!((bus.bump(push) - 1.0).abs() < 0.0001);
+        assert!(bus.speed.abs() < 0.0001);
+    }
+}
@@ -0,0 +1,76 @@
+use rand::Rng;
+
+use crate::level::DEFAULT_LEVEL;
+
+/// Options given on the command line, the browser build never has any.
+pub struct CommandLine {
+    pub seed: u64,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub level: String,
+    /// name of a mission in missions.toml, it picks the level
+    pub mission: Option<String>,
+    /// saved game to carry on with, it picks the level and any mission
+    pub load: Option<String>,
+}
+
+impl CommandLine {
+    pub fn parse() -> CommandLine {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        let mut seed: Option<u64> = None;
+        let mut record: Option<String> = None;
+        let mut replay: Option<String> = None;
+        let mut level = String::from(DEFAULT_LEVEL);
+        let mut mission: Option<String> = None;
+        let mut load: Option<String> = None;
+
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--seed" => {
+                    i += 1;
+                    seed = args.get(i).map(|s| s.parse::<u64>().expect("--seed needs a number"));
+                }
+                "--record" => {
+                    i += 1;
+                    record = Some(args.get(i).expect("--record needs a file name").clone());
+                }
+                "--replay" => {
+                    i += 1;
+                    replay = Some(args.get(i).expect("--replay needs a file name").clone());
+                }
+                "--level" => {
+                    i += 1;
+                    level = args.get(i).expect("--level needs a file name").clone();
+                }
+                "--mission" => {
+                    i += 1;
+                    mission = Some(args.get(i).expect("--mission needs a mission name").clone());
+                }
+                "--load" => {
+                    i += 1;
+                    load = Some(args.get(i).expect("--load needs a file name").clone());
+                }
+                other => println!("Ignoring unknown option {}", other),
+            }
+            i += 1;
+        }
+
+        if load.is_some() && (record.is_some() || replay.is_some()) {
+            println!("Ignoring --record and --replay, they start from a seed not a saved game");
+            record = None;
+            replay = None;
+        }
+        let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+        println!("Using seed {}, repeat this game with --seed {}", seed, seed);
+
+        CommandLine {
+            seed,
+            record,
+            replay,
+            level,
+            mission,
+            load,
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::fs;
+
+use sdl2::keyboard::Keycode;
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_os = "emscripten"))]
+use crate::high_scores::data_dir;
+
+pub const CONTROLS_FILE: &str = "resources/controls.toml";
+#[cfg(not(target_os = "emscripten"))]
+const SAVED_CONTROLS_FILE: &str = "controls.toml";
+
+/// Everything a key can do, the game only ever sees these.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Action {
+    LEFT,
+    RIGHT,
+    THROTTLE,
+    BRAKE,
+    /// forward gear
+    DRIVE,
+    /// reverse gear
+    REVERSE,
+    /// turns the camera a quarter of the way round the bus
+    CAMERANEXT,
+    NORTH,
+    SOUTH,
+    EAST,
+    WEST,
+    PAUSE,
+    /// start from the title screen, play again once it is over
+    START,
+    HIGHSCORES,
+    /// the menu to change keys
+    CONTROLS,
+    QUIT,
+}
+
+/// In the order the controls menu lists them.
+pub const ACTIONS: [Action; 16] = [
+    Action::LEFT, Action::RIGHT, Action::THROTTLE, Action::BRAKE, Action::DRIVE, Action::REVERSE,
+    Action::CAMERANEXT, Action::NORTH, Action::SOUTH, Action::EAST, Action::WEST,
+    Action::PAUSE, Action::START, Action::HIGHSCORES, Action::CONTROLS, Action::QUIT,
+];
+
+impl Action {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Action::LEFT => "steer left",
+            Action::RIGHT => "steer right",
+            Action::THROTTLE => "accelerate",
+            Action::BRAKE => "brake",
+            Action::DRIVE => "forward gear",
+            Action::REVERSE => "reverse gear",
+            Action::CAMERANEXT => "turn camera",
+            Action::NORTH => "camera north",
+            Action::SOUTH => "camera south",
+            Action::EAST => "camera east",
+            Action::WEST => "camera west",
+            Action::PAUSE => "pause",
+            Action::START => "play",
+            Action::HIGHSCORES => "high scores",
+            Action::CONTROLS => "controls",
+            Action::QUIT => "quit",
+        }
+    }
+}
+
+/// One action and the keys for it, by SDL key name.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Binding {
+    pub action: Action,
+    pub keys: Vec<String>,
+}
+
+/// The keys for every action, resources/controls.toml with any changed in the controls menu
+/// on top. The changes are saved next to the high scores, a browser keeps them for the visit.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+pub struct Controls {
+    #[serde(default)]
+    pub binding: Vec<Binding>,
+}
+
+impl Controls {
+    pub fn load() -> Controls {
+        let defaults = read_controls(CONTROLS_FILE).unwrap_or_else(|e| panic!("{}", e));
+        for action in ACTIONS.iter() {
+            if !defaults.binding.iter().any(|b| b.action == *action) {
+                panic!("{}: no keys for {:?}", CONTROLS_FILE, action);
+            }
+        }
+        let mut controls = match read_saved() {
+            Some((filename, text)) => from_toml(&filename, &text).unwrap_or_else(|e| {
+                println!("Ignoring controls that could not be read: {}", e);
+                Controls::default()
+            }),
+            None => Controls::default(),
+        };
+        for binding in defaults.binding {
+            if !controls.binding.iter().any(|b| b.action == binding.action) {
+                controls.binding.push(binding);
+            }
+        }
+        controls
+    }
+
+    pub fn save(&self) {
+        match toml::to_string(self) {
+            Ok(text) => write_saved(&text),
+            Err(e) => println!("Could not save controls: {}", e),
+        }
+    }
+
+    /// What pressing or letting go of keycode does, nothing or more than one thing.
+    pub fn actions(&self, keycode: Keycode) -> Vec<Action> {
+        self.binding.iter()
+            .filter(|b| b.keys.iter().any(|k| Keycode::from_name(k) == Some(keycode)))
+            .map(|b| b.action)
+            .collect()
+    }
+
+    /// The keys for an action to show on screen.
+    pub fn keys(&self, action: Action) -> String {
+        let keys = self.binding.iter().find(|b| b.action == action).map_or(vec![], |b| b.keys.clone());
+        if keys.is_empty() { String::from("no key") } else { keys.join(" or ") }
+    }
+
+    /// keycode does action and nothing else from now on.
+    pub fn bind(&mut self, action: Action, keycode: Keycode) {
+        for binding in self.binding.iter_mut() {
+            binding.keys.retain(|k| Keycode::from_name(k) != Some(keycode));
+        }
+        match self.binding.iter_mut().find(|b| b.action == action) {
+            Some(binding) => binding.keys = vec![keycode.name()],
+            None => self.binding.push(Binding { action, keys: vec![keycode.name()] }),
+        }
+    }
+
+    /// No keys at all for action.
+    pub fn unbind(&mut self, action: Action) {
+        for binding in self.binding.iter_mut().filter(|b| b.action == action) {
+            binding.keys.clear();
+        }
+    }
+}
+
+fn read_controls(filename: &str) -> Result<Controls, String> {
+    let contents = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    from_toml(filename, &contents)
+}
+
+/// Every key has to be one SDL knows the name of.
+fn from_toml(filename: &str, contents: &str) -> Result<Controls, String> {
+    let controls: Controls = toml::from_str(contents).map_err(|e| format!("{}: {}", filename, e))?;
+    for binding in controls.binding.iter() {
+        if let Some(key) = binding.keys.iter().find(|k| Keycode::from_name(k).is_none()) {
+            return Err(format!("{}: {:?} has key {} but there is no key called that", filename, binding.action, key));
+        }
+    }
+    Ok(controls)
+}
+
+#[cfg(not(target_os = "emscripten"))]
+fn read_saved() -> Option<(String, String)> {
+    let path = data_dir().join(SAVED_CONTROLS_FILE);
+    let text = fs::read_to_string(&path).ok()?;
+    Some((path.to_string_lossy().into_owned(), text))
+}
+
+#[cfg(not(target_os = "emscripten"))]
+fn write_saved(text: &str) {
+    let dir = data_dir();
+    let written = fs::create_dir_all(&dir).and_then(|_| fs::write(dir.join(SAVED_CONTROLS_FILE), text));
+    if let Err(e) = written {
+        println!("Could not save controls to {}: {}", dir.display(), e);
+    }
+}
+
+#[cfg(target_os = "emscripten")]
+fn read_saved() -> Option<(String, String)> {
+    None
+}
+
+#[cfg(target_os = "emscripten")]
+fn write_saved(_text: &str) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_controls_have_keys_for_everything() {
+        let controls = read_controls(CONTROLS_FILE).unwrap();
+        for action in ACTIONS.iter() {
+            assert!(controls.binding.iter().any(|b| b.action == *action && !b.keys.is_empty()), "{:?}", action);
+        }
+        assert_eq!(controls.actions(Keycode::LShift), vec![Action::THROTTLE]);
+        assert_eq!(controls.keys(Action::THROTTLE), "Left Shift or Right Shift");
+    }
+
+    #[test]
+    fn a_key_sdl_does_not_know_is_reported() {
+        let text = "[[binding]]\naction = \"brake\"\nkeys = [\"Space\", \"Spacebar\"]\n";
+        let error = from_toml("test.toml", text).unwrap_err();
+        assert!(error.starts_with("test.toml: "), "{}", error);
+        assert!(error.contains("Spacebar"), "{}", error);
+    }
+
+    #[test]
+    fn an_action_the_game_does_not_have_is_reported() {
+        let text = "[[binding]]\naction = \"jump\"\nkeys = [\"Space\"]\n";
+        let error = from_toml("test.toml", text).unwrap_err();
+        assert!(error.starts_with("test.toml: "), "{}", error);
+        assert!(error.contains("jump"), "{}", error);
+    }
+
+    #[test]
+    fn binding_a_key_takes_it_off_anything_else() {
+        let mut controls = read_controls(CONTROLS_FILE).unwrap();
+        controls.bind(Action::PAUSE, Keycode::Space);
+        assert_eq!(controls.actions(Keycode::Space), vec![Action::PAUSE]);
+        assert_eq!(controls.keys(Action::BRAKE), "no key");
+        assert_eq!(controls.keys(Action::PAUSE), "Space");
+    }
+}
@@ -1,8 +1,7 @@
 //use std::ffi::CString;
-use std::ops::{Add, Div};
 use std::time::Instant;
 
-use cgmath::{Basis3, Deg, Matrix4, perspective, Point3, Rotation, Rotation3, vec3, Vector3, Zero};
+use cgmath::{Basis3, Deg, Matrix4, perspective, Point3, Rotation, Rotation3, vec3, Vector3};
 use emscripten_main_loop::MainLoopEvent;
 use rand::Rng;
 use sdl2::{Sdl, VideoSubsystem};
@@ -11,15 +10,13 @@ use sdl2::keyboard::Keycode;
 use sdl2::video::{GLContext, Window};
 
 use crate::{get_start_time, gl, HEIGHT, output_elapsed, vec2point, WIDTH};
-use crate::car_main_player::CarMainPlayer;
+use crate::command_line::CommandLine;
+use crate::controls::{Action, ACTIONS, Controls};
 use crate::flying_camera::{Flying_Camera, PERSPECTIVE_ANGLE};
 use crate::gl_helper::draw_text::DrawText;
-use crate::gl_helper::instance_model::ModelInstance;
 use crate::gl_helper::loading_screen::LoadingScreen;
-use crate::gl_helper::model::Model;
 use crate::gl_helper::shader::create_shader;
 use crate::gl_helper::skybox::{Skybox, SKYBOX_FS, SKYBOX_VS};
-use crate::ground::Ground;
 #[cfg(target_os = "emscripten")]
 use crate::handle_javascript::end_game;
 #[cfg(target_os = "emscripten")]
@@ -27,113 +24,98 @@ use crate::handle_javascript::start_game;
 //#[cfg(target_os = "emscripten")]
 //use crate::handle_javascript::start_javascript_play_sound;
 use crate::handle_javascript::write_stats_data;
+use crate::high_scores::{HighScore, HighScores, MAX_HIGH_SCORES, MAX_NAME, today};
+use crate::level::Level;
 use crate::map_display::MapDisplay;
+use crate::mission::{clock, load_missions, Mission, MissionProgress, MISSIONS_FILE, MissionState};
+use crate::recording::{Frame, InputRecorder, InputReplay};
+#[cfg(not(target_os = "emscripten"))]
+use crate::save_game::default_save_file;
+use crate::save_game::SaveGame;
+use crate::scenery::SceneryType;
 //use crate::openglshadow::OpenglShadow;
-use crate::passengers::{Passenger, PASSENGER_SCALE};
-use crate::sound::{load_sound, play, SCOOP, EXPLOSION};
-use crate::special_effects::SpecialEffects;
-
-pub const GROUND: f32 = 0.01;
-const TARGET_FPS: u128 = 40;
-const MAX_PASSENGERS: usize = 10;
+use crate::sound::{load_sound, play, SCOOP, EXPLOSION, WARNING};
+use crate::world::{Input, World, WorldEvent};
+use crate::world_renderer::WorldRenderer;
+
+const TARGET_FPS: u128 = 60;
+// the world is always stepped at this rate whatever the frame rate is
+const SIMULATION_STEP: f32 = 1.0 / 40.0;
+// a long pause, like the first load in a browser, would otherwise run lots of steps in one go
+const MAX_FRAME_DELTA: f32 = 0.25;
+
+/// Where the game is up to, each has its own update and render.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+enum GameState {
+    /// loading screens up while the text and models are set up
+    LOADING,
+    TITLE,
+    PLAYING,
+    /// the world is drawn but not stepped
+    PAUSED,
+    /// the results, with a name typed in if it made the high scores
+    GAMEOVER,
+    HIGHSCORES,
+    /// changing which keys do what
+    CONTROLS,
+}
 
 pub struct Runtime {
     //opengl_shadow: OpenglShadowPointAllDirections,
     //opengl_shadow: OpenglShadow,
     no_shadow_shader: u32,
-    loaded: bool,
+    state: GameState,
     now: Instant,
     last_time_called: u128,
     rate_debug: String,
     sdl: Sdl,
-    _video: VideoSubsystem,
+    video: VideoSubsystem,
     window: Window,
     _gl_context: GLContext,
     pub gl: std::rc::Rc<gl::Gl>,
     pub camera: Flying_Camera,
-    pub left: bool,
-    pub right: bool,
-    pub up: bool,
-    pub down: bool,
-    pub forward: bool,
-    pub backward: bool,
-    pub space: bool,
-    pub ground: Option<Ground>,
-    score: i32,
-    lives: i32,
+    pub input: Input,
+    pub world: Option<World>,
+    world_renderer: Option<WorldRenderer>,
     tick: i128,
-    player_avitar: CarMainPlayer,
+    accumulator: f32,
     camera_angle: f32,
     draw_text: Option<DrawText>,
-    special_effects: SpecialEffects,
     flash_message: Vec<String>,
-    flash_message_countdown: i128,
-    game_over: bool,
+    flash_message_countdown: f32,
     bernard: i64,
-    slow_loading_items: bool,
     loading_screen1: LoadingScreen,
     loading_screen2: LoadingScreen,
     map_display: MapDisplay,
     sky_box: Skybox,
-    original_passenger_to_copy:Vec<ModelInstance>,
-    passengers: Vec<Passenger>,
-}
-
-
-static mut GLOBAL_ID: u128 = 0;
-
-fn get_next_id() -> u128 {
-    unsafe {
-        GLOBAL_ID = GLOBAL_ID + 1;
-        GLOBAL_ID
-    }
-}
-
-#[derive(Clone)]
-pub struct MovementAndCollision {
-    pub id: u128,
-    pub radius: f32,
-    pub position: Vector3<f32>,
-    pub been_hit: bool,
-    pub moved: bool,
-}
-
-impl Default for MovementAndCollision {
-    fn default() -> Self {
-        MovementAndCollision {
-            id: get_next_id(),
-            radius: 0.0,
-            position: Vector3::zero(),
-            been_hit: false,
-            moved: false,
-        }
-    }
-}
-
-
-impl MovementAndCollision {
-    pub fn new(radius: f32, position: Vector3<f32>) -> MovementAndCollision {
-        MovementAndCollision {
-            radius,
-            position,
-            been_hit: false,
-            moved: false,
-            ..MovementAndCollision::default()
-        }
-    }
-
-}
-
-pub(crate) trait Render {
-    fn render(&mut self, gl: &gl::Gl, view: &Matrix4<f32>, projection: &Matrix4<f32>, our_shader: u32);
+    command_line: CommandLine,
+    level: Level,
+    /// where playing again starts from
+    first_level: Level,
+    mission: Option<Mission>,
+    recorder: Option<InputRecorder>,
+    replay: Option<InputReplay>,
+    /// from --load, becomes the world once loading is done
+    saved_game: Option<SaveGame>,
+    high_scores: HighScores,
+    /// the name being typed in after a game good enough for the high scores
+    name_entry: Option<String>,
+    /// where this game went in the high scores, to pick it out in the table
+    high_score_place: Option<usize>,
+    seconds_played: f32,
+    controls: Controls,
+    /// the action picked in the controls menu
+    controls_selected: usize,
+    /// waiting for the new key for the picked action
+    rebinding: bool,
+    /// where leaving the controls menu goes back to
+    controls_back: GameState,
 }
 
-pub(crate) trait Update {
-    fn update(&mut self, delta: f32, ground: &Ground);
-}
 
 impl Runtime {
-    pub(crate) fn new() -> Runtime {
+    pub(crate) fn new(command_line: CommandLine) -> Runtime {
         let start = get_start_time();
         let sdl = sdl2::init().unwrap();
 
@@ -168,63 +150,90 @@ impl Runtime {
             ..Flying_Camera::default()
         };
 
-        let special_effects = SpecialEffects::new(&gl);
-
         unsafe { gl.Enable(gl::BLEND); }
 
         #[cfg(not(target_os = "emscripten"))]
             load_sound(&sdl);
 
-        let player = CarMainPlayer::new(&gl);
-
         let start_block = Instant::now();
         //let opengl_shadow = OpenglShadow::new(&gl);
         let duration = start_block.elapsed();
         println!("Time elapsed in openglshadow is: {:?}", duration);
 
 
+        let mission = command_line.mission.as_ref().map(|name| {
+            load_missions(MISSIONS_FILE).unwrap_or_else(|e| panic!("{}", e))
+                .into_iter().find(|m| &m.name == name)
+                .unwrap_or_else(|| panic!("no mission called {} in {}", name, MISSIONS_FILE))
+        });
+        let saved_game = command_line.load.as_ref().map(|filename| {
+            SaveGame::read(filename).unwrap_or_else(|e| panic!("{}", e))
+        });
+        let level = match &saved_game {
+            Some(saved_game) => saved_game.level.clone(),
+            None => {
+                let level_file = mission.as_ref().map_or(command_line.level.clone(), |m| m.level.clone());
+                Level::load(&level_file).unwrap_or_else(|e| panic!("{}", e))
+            }
+        };
+
+        let replay = command_line.replay.as_ref().map(|filename| {
+            InputReplay::open(filename).expect("could not read replay file")
+        });
+        let seed = replay.as_ref().map_or(command_line.seed, |r| r.seed);
+        let recorder = command_line.record.as_ref().map(|filename| {
+            InputRecorder::create(filename, seed).expect("could not create recording file")
+        });
+
         //let opengl_shadow = OpenglShadowPointAllDirections::new(&gl);
         let runtime = Runtime {
             //opengl_shadow,
             no_shadow_shader: create_shader(&gl, SKYBOX_VS, SKYBOX_FS, None),
-            loaded: false,
+            state: GameState::LOADING,
             now: Instant::now(),
             last_time_called: 0,
             sdl,
-            _video: video,
+            video,
             window,
             _gl_context: gl_context,
             gl: gl_orig,
             camera,
-            ground: None,
-            left: false,
-            right: false,
-            up: false,
-            down: false,
-            forward: false,
-            backward: false,
-            space: false,
-            score: 0,
-            lives: 5,
+            input: Input::default(),
+            world: None,
+            world_renderer: None,
             tick: 0,
-            player_avitar: player,
+            accumulator: 0.0,
             camera_angle: 0.0,
             draw_text: None,
-            special_effects,
             flash_message: vec![],
-            flash_message_countdown: 0,
-            game_over: false,
+            flash_message_countdown: 0.0,
             rate_debug: "".to_string(),
-            slow_loading_items: true,
             bernard: 0,
             loading_screen1: LoadingScreen::new(&gl, "resources/loading.png"),
             loading_screen2: LoadingScreen::new(&gl, "resources/loading2.png"),
-            map_display: MapDisplay::new(&gl),
+            map_display: MapDisplay::new(&gl, &level.map),
             sky_box: Skybox::new(&gl, "resources/sky.png"),
-            original_passenger_to_copy: vec![],
-            passengers: vec![],
+            command_line,
+            first_level: level.clone(),
+            level,
+            mission,
+            recorder,
+            replay,
+            saved_game,
+            high_scores: HighScores::load(),
+            name_entry: None,
+            high_score_place: None,
+            seconds_played: 0.0,
+            controls: Controls::load(),
+            controls_selected: 0,
+            rebinding: false,
+            controls_back: GameState::TITLE,
         };
         output_elapsed(start, "Time elapsed in game() is");
+        #[cfg(target_os = "emscripten")]
+            unsafe {
+            start_game();
+        }
         runtime
     }
 }
@@ -235,7 +244,7 @@ impl emscripten_main_loop::MainLoop for Runtime {
         self.tick = self.tick + 1;
 
 
-        let debug_start = Instant::now();
+        let _debug_start = Instant::now();
 
         let time_now = self.now.elapsed().as_millis();
         let diff = time_now - self.last_time_called;
@@ -251,55 +260,32 @@ impl emscripten_main_loop::MainLoop for Runtime {
 
         let fps = 1.0 / delta as f32;
 
-        // just for browser, big drop in rate on first load
-        let update_delta = delta; //if fps > 5.0 { 1.0 } else { fps };
-
         if self.tick % 20 == 0 {
             self.rate_debug = format!("{} - {:2.2}", self.bernard, fps);
         }
 
-
-        if self.loaded == false {
-            self.loaded = true;
-            #[cfg(target_os = "emscripten")]
-                unsafe {
-                start_game();
-            }
-        }
         unsafe {
             self.gl.Enable(gl::DEPTH_TEST);
         }
 
-        let end_status = if !self.slow_loading_items {
-            self.game_playing_loop(debug_start, update_delta)
-        } else {
-            unsafe {
-                self.gl.ClearColor(0.0, 0.0, 0.0, 1.0);
-                self.gl.Clear(gl::DEPTH_BUFFER_BIT | gl::COLOR_BUFFER_BIT);
-            }
-            if self.draw_text.is_none() {
-                self.loading_screen1.render(&self.gl);
-            } else {
-                self.loading_screen2.render(&self.gl);
-            }
-            self.window.gl_swap_window();
-
-            if self.tick > 10 && self.draw_text.is_none() {
-                self.setup_text_if_not_loaded();
-            }
-
-            if self.tick > 20 && self.ground.is_none() {
-                self.ground = Some(Ground::new(&self.gl));
-                self.slow_loading_items = false;
-                //let mut original_passenger_animation = self.create_passenger();
-                self.original_passenger_to_copy = self.create_passenger();
-            }
-            MainLoopEvent::Continue
+        let mut end_status = match self.state {
+            GameState::LOADING => self.update_loading(),
+            GameState::PLAYING => self.update_playing(delta),
+            GameState::TITLE | GameState::PAUSED | GameState::GAMEOVER | GameState::HIGHSCORES | GameState::CONTROLS => MainLoopEvent::Continue,
         };
-
+        self.render(delta);
+        self.camera.save_position();
+        if let MainLoopEvent::Continue = end_status {
+            end_status = self.handle_keyboard();
+        }
 
         match end_status {
             MainLoopEvent::Terminate => {
+                #[cfg(not(target_os = "emscripten"))]
+                    self.save_on_quit();
+                if let Some(recorder) = self.recorder.as_mut() {
+                    recorder.finish().expect("could not finish recording");
+                }
                 #[cfg(target_os = "emscripten")]
                     unsafe {
                     end_game();
@@ -308,12 +294,20 @@ impl emscripten_main_loop::MainLoop for Runtime {
             MainLoopEvent::Continue => {}
         }
 
+        /*
+                let mut list: Vec<String> = Vec::new();
+                list.push(format!("level {} score {} lives {} {}", self.level, self.score, self.lives, self.player_avitar.msg));
+                let update: String = list.join("\n");
+
+                #[allow(temporary_cstring_as_ptr)]
+                    write_stats_data(CString::new(update).to_owned().unwrap().as_ptr());
+        */
+
+        //output_elapsed(_debug_start, "time elapsed for gameloop");
         end_status
     }
 }
 
-
-
 impl Runtime {
     fn setup_text_if_not_loaded(&mut self) {
         let start_block = Instant::now();
@@ -322,64 +316,60 @@ impl Runtime {
         println!("Time elapsed in drawtext is: {:?}", duration);
         self.draw_text = Some(draw_text);
     }
-    fn position_camera_matrix(&self) -> Matrix4<f32> {
+    fn position_camera_matrix(&self, bus_position: Vector3<f32>) -> Matrix4<f32> {
         let rotation: Basis3<f32> = Rotation3::from_angle_y(Deg(self.camera_angle));
 
         let away: Vector3<f32> = rotation.rotate_vector(vec3(0.0, 0.0, 2.0));
-        let mut here = self.player_avitar.movement_collision.position.clone() + away;
+        let mut here = bus_position + away;
         here.y = here.y + 0.5;
         let matrix =
             Matrix4::look_at(vec2point(here),
-                             vec2point(self.player_avitar.movement_collision.position),
+                             vec2point(bus_position),
                              vec3(0.0, 1.0, 0.0));
         matrix
     }
 
+    /// Keys become actions here, apart from typing a name and changing keys in the controls menu.
     fn handle_keyboard(&mut self) -> MainLoopEvent {
         let mut return_status = emscripten_main_loop::MainLoopEvent::Continue;
         let mut events = self.sdl.event_pump().unwrap();
 
         for event in events.poll_iter() {
             match event {
-                Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
+                Event::Quit { .. } => {
                     return_status = emscripten_main_loop::MainLoopEvent::Terminate;
                 }
-                Event::KeyDown { keycode: Some(Keycode::Left), .. } => {
-                    self.left = true;
-                    self.right = false;
-                }
-                Event::KeyDown { keycode: Some(Keycode::Right), .. } => {
-                    self.right = true;
-                    self.left = false;
+                Event::TextInput { text, .. } if self.name_entry.is_some() => {
+                    let name = self.name_entry.as_mut().unwrap();
+                    for c in text.chars().filter(|c| c.is_ascii_graphic() || *c == ' ') {
+                        if name.len() < MAX_NAME {
+                            name.push(c);
+                        }
+                    }
                 }
-                Event::KeyDown { keycode: Some(Keycode::Up), .. } => {
-                    self.up = true;
-                    self.down = false
+                Event::KeyDown { keycode: Some(Keycode::Backspace), .. } if self.name_entry.is_some() => {
+                    self.name_entry.as_mut().unwrap().pop();
                 }
-                Event::KeyDown { keycode: Some(Keycode::Down), .. } => {
-                    self.down = true;
-                    self.up = false
+                Event::KeyDown { keycode: Some(Keycode::Return), .. } if self.name_entry.is_some() => {
+                    self.enter_high_score();
                 }
-                Event::KeyDown { keycode: Some(Keycode::LShift), .. } => {
-                    self.forward = true;
+                // keys are letters of the name while it is typed
+                Event::KeyDown { .. } if self.name_entry.is_some() => {}
+                Event::KeyDown { keycode: Some(keycode), repeat: false, .. } if self.state == GameState::CONTROLS => {
+                    self.controls_menu_key(keycode);
                 }
-                Event::KeyDown { keycode: Some(Keycode::RShift), .. } => {
-                    self.backward = true;
+                Event::KeyDown { keycode: Some(keycode), repeat: false, .. } => {
+                    for action in self.controls.actions(keycode) {
+                        if let MainLoopEvent::Terminate = self.action(action, true) {
+                            return_status = emscripten_main_loop::MainLoopEvent::Terminate;
+                        }
+                    }
                 }
-                Event::KeyDown { keycode: Some(Keycode::Space), .. } => {
-                    self.space = true;
+                Event::KeyUp { keycode: Some(keycode), .. } => {
+                    for action in self.controls.actions(keycode) {
+                        self.action(action, false);
+                    }
                 }
-                Event::KeyDown { keycode: Some(Keycode::N), .. } => { self.camera_angle = 0.0; }
-                Event::KeyDown { keycode: Some(Keycode::S), .. } => { self.camera_angle = 180.0; }
-                Event::KeyDown { keycode: Some(Keycode::E), .. } => { self.camera_angle = 90.0; }
-                Event::KeyDown { keycode: Some(Keycode::W), .. } => { self.camera_angle = 270.0; }
-                Event::KeyUp { keycode: Some(Keycode::Left), .. } => { self.left = false; }
-                Event::KeyUp { keycode: Some(Keycode::Right), .. } => { self.right = false; }
-                Event::KeyUp { keycode: Some(Keycode::Up), .. } => { self.up = false }
-                Event::KeyUp { keycode: Some(Keycode::Down), .. } => { self.down = false }
-                Event::KeyUp { keycode: Some(Keycode::LShift), .. } => { self.forward = false }
-                Event::KeyUp { keycode: Some(Keycode::RShift), .. } => { self.backward = false }
-                Event::KeyUp { keycode: Some(Keycode::Space), .. } => { self.space = false; }
 
                 _ => {}
             }
@@ -388,19 +378,246 @@ impl Runtime {
         return_status
     }
 
-    fn game_playing_loop(&mut self, _debug_start: Instant, update_delta: f32) -> MainLoopEvent {
-        let humans = self.passengers.iter().filter(|p| !p.zombie).count();
+    /// An action's key pressed, or let go of when pressed is false.
+    fn action(&mut self, action: Action, pressed: bool) -> MainLoopEvent {
+        match action {
+            Action::QUIT if pressed => return MainLoopEvent::Terminate,
+            Action::PAUSE if pressed && self.state == GameState::PLAYING => {
+                self.state = GameState::PAUSED;
+            }
+            Action::PAUSE if pressed && self.state == GameState::PAUSED => {
+                self.state = GameState::PLAYING;
+            }
+            _ if self.replay.is_some() => {}
+            Action::START if pressed && self.state == GameState::TITLE => {
+                self.state = GameState::PLAYING;
+            }
+            Action::START if pressed && self.state == GameState::GAMEOVER => {
+                self.play_again();
+            }
+            Action::START if pressed && self.state == GameState::HIGHSCORES => {
+                if self.world_over() {
+                    self.play_again();
+                } else {
+                    self.state = GameState::PLAYING;
+                }
+            }
+            Action::HIGHSCORES if pressed && (self.state == GameState::TITLE || self.state == GameState::GAMEOVER) => {
+                self.state = GameState::HIGHSCORES;
+            }
+            Action::CONTROLS if pressed && (self.state == GameState::TITLE || self.state == GameState::PAUSED) => {
+                self.controls_back = self.state;
+                self.state = GameState::CONTROLS;
+                self.input = Input::default();
+            }
+            // driving only while playing, but keys let go of are always noticed
+            _ if pressed && self.state != GameState::PLAYING => {}
+            Action::LEFT => {
+                self.input.left = pressed;
+                if pressed {
+                    self.input.right = false;
+                }
+            }
+            Action::RIGHT => {
+                self.input.right = pressed;
+                if pressed {
+                    self.input.left = false;
+                }
+            }
+            Action::DRIVE => {
+                self.input.drive = pressed;
+                if pressed {
+                    self.input.reverse = false;
+                }
+            }
+            Action::REVERSE => {
+                self.input.reverse = pressed;
+                if pressed {
+                    self.input.drive = false;
+                }
+            }
+            Action::THROTTLE => { self.input.throttle = pressed; }
+            Action::BRAKE => { self.input.brake = pressed; }
+            Action::CAMERANEXT if pressed => { self.camera_angle = (self.camera_angle + 90.0) % 360.0; }
+            Action::NORTH if pressed => { self.camera_angle = 0.0; }
+            Action::SOUTH if pressed => { self.camera_angle = 180.0; }
+            Action::EAST if pressed => { self.camera_angle = 90.0; }
+            Action::WEST if pressed => { self.camera_angle = 270.0; }
+            _ => {}
+        }
+        MainLoopEvent::Continue
+    }
+
+    /// The controls menu always uses up, down, return, backspace and escape so a bad choice
+    /// of keys can be put right.
+    fn controls_menu_key(&mut self, keycode: Keycode) {
+        let action = ACTIONS[self.controls_selected];
+        if self.rebinding {
+            self.rebinding = false;
+            // escape gives up rather than being the new key
+            if keycode != Keycode::Escape {
+                self.controls.bind(action, keycode);
+                self.controls.save();
+            }
+            return;
+        }
+        match keycode {
+            Keycode::Up => self.controls_selected = (self.controls_selected + ACTIONS.len() - 1) % ACTIONS.len(),
+            Keycode::Down => self.controls_selected = (self.controls_selected + 1) % ACTIONS.len(),
+            Keycode::Return => self.rebinding = true,
+            Keycode::Backspace => {
+                self.controls.unbind(action);
+                self.controls.save();
+            }
+            Keycode::Escape => self.state = self.controls_back,
+            _ => {}
+        }
+    }
+    /// Text first, then the world and its models, with a loading screen up meanwhile.
+    fn update_loading(&mut self) -> MainLoopEvent {
+        if self.tick > 10 && self.draw_text.is_none() {
+            self.setup_text_if_not_loaded();
+        }
+
+        if self.tick > 20 && self.world.is_none() {
+            let world = match self.saved_game.take() {
+                Some(saved_game) => {
+                    self.seconds_played = saved_game.seconds_played;
+                    World::from_save_game(saved_game)
+                }
+                None => {
+                    let seed = self.replay.as_ref().map_or(self.command_line.seed, |r| r.seed);
+                    let mut world = World::new(seed, &self.level);
+                    if let Some(mission) = &self.mission {
+                        world.start_mission(mission.clone());
+                        self.flash_message.push(mission.description.clone());
+                    }
+                    world
+                }
+            };
+            self.world_renderer = Some(WorldRenderer::new(&self.gl, &world));
+            self.world = Some(world);
+            self.flash_message.push(format!("level {}", self.level.name));
+            self.flash_message_countdown = 2.5;
+            // nobody is there to press return for a replay
+            self.state = if self.replay.is_some() { GameState::PLAYING } else { GameState::TITLE };
+        }
+        MainLoopEvent::Continue
+    }
+
+    /// Run one fixed step of the world, false when a replay has run out of frames.
+    fn simulation_step(&mut self) -> bool {
+        let mut update_delta = SIMULATION_STEP;
+        if let Some(replay) = self.replay.as_mut() {
+            match replay.next_frame() {
+                Some(frame) => {
+                    update_delta = frame.delta;
+                    self.input = frame.input;
+                    self.camera_angle = frame.camera_angle;
+                }
+                None => {
+                    let world = self.world.as_ref().unwrap();
+                    println!("Replay finished tick={} score={} lives={}", world.tick, world.score, world.lives);
+                    return false;
+                }
+            }
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            let frame = Frame { delta: update_delta, input: self.input, camera_angle: self.camera_angle };
+            recorder.record(&frame).expect("could not write recording");
+        }
+        let world = self.world.as_mut().unwrap();
+        world.step(update_delta, &self.input);
+        if !world.game_over && !world.level_complete {
+            self.seconds_played += update_delta;
+        }
+        self.handle_world_events();
+        true
+    }
+
+    fn update_playing(&mut self, frame_delta: f32) -> MainLoopEvent {
+        self.accumulator += frame_delta.min(MAX_FRAME_DELTA);
+        while self.accumulator >= SIMULATION_STEP {
+            self.accumulator -= SIMULATION_STEP;
+            if !self.simulation_step() {
+                return MainLoopEvent::Terminate;
+            }
+            if self.world_over() {
+                self.game_over();
+                break;
+            }
+        }
+        MainLoopEvent::Continue
+    }
+
+    /// Lives gone, the last level done or the mission over one way or the other.
+    fn world_over(&self) -> bool {
+        let world = self.world.as_ref().unwrap();
+        world.game_over || world.level_complete || world.mission.as_ref().is_some_and(|m| m.finished())
+    }
+
+    fn render(&mut self, frame_delta: f32) {
+        unsafe {
+            self.gl.ClearColor(0.0, 0.0, 0.0, 1.0);
+            self.gl.Clear(gl::DEPTH_BUFFER_BIT | gl::COLOR_BUFFER_BIT);
+        }
+        match self.state {
+            GameState::LOADING => self.render_loading(),
+            GameState::TITLE => {
+                self.render_world();
+                self.render_title();
+            }
+            GameState::PLAYING => {
+                self.render_world();
+                self.render_hud(frame_delta);
+            }
+            GameState::PAUSED => {
+                self.render_world();
+                self.render_hud(0.0);
+                self.render_paused();
+            }
+            GameState::GAMEOVER => {
+                self.render_world();
+                self.render_game_over();
+            }
+            GameState::HIGHSCORES => {
+                self.render_world();
+                self.render_high_scores();
+            }
+            GameState::CONTROLS => {
+                self.render_world();
+                self.render_controls();
+            }
+        }
+        self.window.gl_swap_window();
+    }
+
+    fn render_loading(&self) {
+        if self.draw_text.is_none() {
+            self.loading_screen1.render(&self.gl);
+        } else {
+            self.loading_screen2.render(&self.gl);
+        }
+    }
+
+    /// The bus between the last step and the next one.
+    fn bus_position(&self) -> Vector3<f32> {
+        let alpha = self.accumulator / SIMULATION_STEP;
+        self.world.as_ref().unwrap().bus.movement_collision.interpolated_position(alpha)
+    }
 
-        self.add_some_passengers_if_required(humans);
+    fn render_world(&mut self) {
+        let alpha = self.accumulator / SIMULATION_STEP;
+        let bus_position = self.bus_position();
+        let world = self.world.as_ref().unwrap();
         let projection: Matrix4<f32> =
             perspective(Deg(PERSPECTIVE_ANGLE), WIDTH as f32 / HEIGHT as f32, 0.01, 100.0);
 
-        let view = self.position_camera_matrix();
+        let view = self.position_camera_matrix(bus_position);
 
-        self.ground.as_mut().unwrap().set_player_position(self.player_avitar.movement_collision.position.x, self.player_avitar.movement_collision.position.z);
-
-        if !self.game_over {
-            self.ground.as_mut().unwrap().update(&self.gl, self.player_avitar.movement_collision.position, self.camera_angle, update_delta);
+        // the tiles around the bus only change while it can move
+        if self.state == GameState::TITLE || self.state == GameState::PLAYING {
+            self.world_renderer.as_mut().unwrap().update(&world.ground, bus_position, self.camera_angle);
         }
 
 /*
@@ -409,210 +626,412 @@ impl Runtime {
             self.camera_angle);
  */
 
-        self.special_effects.update(update_delta, &self.ground.as_ref().unwrap());
-
         //self.slow_performance_render_shadow(&projection, &view);
 
         unsafe {
-            self.gl.ClearColor(0.0, 0.0, 0.0, 1.0);
-            self.gl.Clear(gl::DEPTH_BUFFER_BIT | gl::COLOR_BUFFER_BIT);
             self.gl.UseProgram(self.no_shadow_shader);
         }
 
-        self.ground.as_mut().unwrap().render(&self.gl, &view, &projection, self.player_avitar.movement_collision.position, self.camera_angle,
-                                             self.no_shadow_shader, &mut self.passengers, self.tick);
-        self.player_avitar.render(&self.gl, &view, &projection, self.no_shadow_shader);
+        self.world_renderer.as_mut().unwrap().render(&self.gl, world, alpha, &view, &projection, self.no_shadow_shader);
+        self.sky_box.render(&self.gl, &view, &projection, bus_position);
 
-        self.special_effects.render(&self.gl, &view, &projection, self.no_shadow_shader);
-        self.sky_box.render(&self.gl, &view, &projection, self.player_avitar.movement_collision.position);
+        self.map_display.render(&self.gl, bus_position);
+    }
 
-        self.map_display.render(&self.gl, self.player_avitar.movement_collision.position);
-
-        if !self.game_over {
-            if self.lives <= 0 {
-                self.forward = false;
-                self.game_over = true;
-            } else {
-                if self.draw_text.is_some() {
-                    let under_landscape = self.ground.as_ref().unwrap().currently_under_landscape(self.player_avitar.movement_collision.position.x, self.player_avitar.movement_collision.position.z);
-
-                    let compass = match self.camera_angle as i32 {
-                        0 => "North",
-                        180 => "South",
-                        90 => "East",
-                        270 => "West",
-                        _ => ""
-                    };
+    /// Score, lives and the rest along the top, flash messages in the middle. frame_delta
+    /// counts the flash messages down, 0.0 holds them.
+    fn render_hud(&mut self, frame_delta: f32) {
+        let bus_position = self.bus_position();
+        let world = self.world.as_ref().unwrap();
+        let under_landscape = world.ground.currently_under_landscape(bus_position.x, bus_position.z);
+
+        let compass = match self.camera_angle as i32 {
+            0 => "North",
+            180 => "South",
+            90 => "East",
+            270 => "West",
+            _ => ""
+        };
 
-                    let status = format!("score={} lives={} camera={}", self.score, self.lives, compass);
-                    self.draw_text.as_ref().unwrap().draw_text(&self.gl, &status, 2.0, HEIGHT as f32 - 30.0, vec3(1.0, 1.0, 0.0), 1.0);
-                    let status = format!("humans={} off_road={} ", humans, self.player_avitar.off_road.round());
-                    self.draw_text.as_ref().unwrap().draw_text(&self.gl, &status, 2.0, HEIGHT as f32 - 60.0, vec3(1.0, 1.0, 0.0), 1.0);
-
-                    let status = format!("road={} {} {}", under_landscape.filename,self.player_avitar.msg,self.rate_debug);
-                    self.draw_text.as_ref().unwrap().draw_text(&self.gl, &status, 2.0, 0.0, vec3(1.0, 1.0, 0.0), 1.0);
-                    if self.flash_message_countdown > 0 {
-                        self.flash_message_countdown = self.flash_message_countdown -1;
-                        let mut screen_y = HEIGHT as f32 * 0.75;
-                        for msg in self.flash_message.iter() {
-                            self.draw_text.as_ref().unwrap().draw_text(
-                                &self.gl, msg, 10.0, screen_y, vec3(1.0, 1.0, 0.0),1.5);
-                            screen_y = screen_y - 60.0;
-                        }
-                        if self.flash_message_countdown <= 0 {
-                            self.flash_message.clear();
-                        }
-                    }
-                }
-            }
-        } else {
-            #[cfg(target_os = "emscripten")]
-            let where_x = (self.tick as f32 ) % (WIDTH as f32 * 1.25) - 100.0;
-            #[cfg(not(target_os = "emscripten"))]
-            let where_x = (self.tick as f32  / 100000.0) % (WIDTH as f32 * 1.25) - 100.0;
-            self.draw_text.as_ref().unwrap().draw_text(&self.gl, "Game over...", where_x, HEIGHT as f32 * 0.75, vec3(1.0, 1.0, 0.0), 2.0);
-            let status = format!("score={}", self.score, );
-            self.draw_text.as_ref().unwrap().draw_text(&self.gl, &status, where_x, HEIGHT as f32 * 0.5, vec3(1.0, 1.0, 0.0), 2.0);
+        let score = if world.spawn_policy.endless() { format!("{}", world.score) } else { format!("{}/{}", world.score, world.level.target_score) };
+        let status = format!("score={} lives={} damage={}% camera={}", score, world.lives, world.bus_damage, compass);
+        self.draw_text.as_ref().unwrap().draw_text(&self.gl, &status, 2.0, HEIGHT as f32 - 30.0, vec3(1.0, 1.0, 0.0), 1.0);
+        let status = format!("humans={} off_road={} {}", world.humans(), world.bus.off_road.round(), world.spawn_policy.status().unwrap_or_default());
+        self.draw_text.as_ref().unwrap().draw_text(&self.gl, &status, 2.0, HEIGHT as f32 - 60.0, vec3(1.0, 1.0, 0.0), 1.0);
+
+        if !world.bus_stops.is_empty() {
+            let destinations: Vec<String> = world.riders.iter().map(|r| {
+                let name = &world.bus_stops[r.destination].name;
+                if r.turning() { format!("{} (turning)", name) } else if r.sick() { format!("{} (sick)", name) } else { name.clone() }
+            }).collect();
+            let status = format!("on board {}/{} {}", world.riders.len(), world.level.bus_capacity, destinations.join(", "));
+            self.draw_text.as_ref().unwrap().draw_text(&self.gl, &status, 2.0, HEIGHT as f32 - 90.0, vec3(1.0, 1.0, 0.0), 1.0);
+        }
+        if let Some(mission) = &world.mission {
+            let status = format!("{}: {}", mission.mission.name, mission.progress());
+            self.draw_text.as_ref().unwrap().draw_text(&self.gl, &status, 2.0, HEIGHT as f32 - 120.0, vec3(1.0, 1.0, 0.0), 1.0);
         }
 
-
-        self.window.gl_swap_window();
-
-
-        // update here to remove flicker.
-        if !self.game_over {
-            for index in (0..self.passengers.len()).rev() {
-                let passenger = self.passengers.get_mut(index).unwrap();
-                    let (remove, add_score, zombie_explode) = passenger.update(update_delta, &self.ground.as_ref().unwrap(), &self.camera, self.tick, &mut self.special_effects, self.player_avitar.movement_collision.position);
-                    if remove {
-                        self.passengers.remove(index);
-                    }
-                    if add_score {
-                        play(SCOOP);
-                        self.score = self.score + 1;
-                        self.flash_message.push(String::from("passenger picked up"));
-                        self.flash_message_countdown = 60;
-                    }
-                    if zombie_explode {
-                        self.lives = self.lives - 1;
-                        self.flash_message.push(String::from("zombie exploded near you"));
-                        self.flash_message_countdown = 100;
-                        //self.player_avitar.reset(); // dont reset as miss explosion
-                        let mut over_bus = self.player_avitar.movement_collision.position.clone();
-                        over_bus.y = over_bus.y + 0.3;
-                        self.special_effects.explosion(over_bus);
-                    }
-                    if self.player_avitar.off_road_too_much() {
-                        play(EXPLOSION);
-                        self.lives = self.lives - 1;
-                        self.flash_message.push(String::from("off road too long"));
-                        self.flash_message_countdown = 100;
-                    }
+        let status = format!("road={} {} {}", under_landscape.filename, world.bus.msg, self.rate_debug);
+        self.draw_text.as_ref().unwrap().draw_text(&self.gl, &status, 2.0, 0.0, vec3(1.0, 1.0, 0.0), 1.0);
+        if self.flash_message_countdown > 0.0 {
+            self.flash_message_countdown -= frame_delta;
+            let mut screen_y = HEIGHT as f32 * 0.75;
+            for msg in self.flash_message.iter() {
+                self.draw_text.as_ref().unwrap().draw_text(
+                    &self.gl, msg, 10.0, screen_y, vec3(1.0, 1.0, 0.0),1.5);
+                screen_y -= 60.0;
+            }
+            if self.flash_message_countdown <= 0.0 {
+                self.flash_message.clear();
             }
-            self.player_avitar.update(update_delta, &self.ground.as_ref().unwrap(), &self.camera, self.tick,);
         }
+    }
 
-        self.camera.save_position();
+    fn render_title(&self) {
+        let text = self.draw_text.as_ref().unwrap();
+        let yellow = vec3(1.0, 1.0, 0.0);
+        text.draw_text(&self.gl, "Bus Zombie", 20.0, HEIGHT as f32 - 100.0, yellow, 3.0);
+        let world = self.world.as_ref().unwrap();
+        let what = match &world.mission {
+            Some(mission) => format!("mission {}", mission.mission.name),
+            None => format!("level {}", world.level.name),
+        };
+        text.draw_text(&self.gl, &what, 20.0, HEIGHT as f32 - 160.0, yellow, 1.5);
+        // a game loaded with --load carries on where it was
+        let play = if world.tick > 0 { "carry on" } else { "play" };
+        let play = format!("press {} to {}", self.controls.keys(Action::START), play);
+        text.draw_text(&self.gl, &play, 20.0, HEIGHT as f32 * 0.4, yellow, 1.5);
+        let help = format!("{} high scores, {} controls", self.controls.keys(Action::HIGHSCORES), self.controls.keys(Action::CONTROLS));
+        text.draw_text(&self.gl, &help, 20.0, HEIGHT as f32 * 0.4 - 50.0, yellow, 1.0);
+        let help = format!("{} pause, {} quit", self.controls.keys(Action::PAUSE), self.controls.keys(Action::QUIT));
+        text.draw_text(&self.gl, &help, 20.0, HEIGHT as f32 * 0.4 - 80.0, yellow, 1.0);
+    }
 
+    fn render_paused(&self) {
+        let text = self.draw_text.as_ref().unwrap();
+        let yellow = vec3(1.0, 1.0, 0.0);
+        text.draw_text(&self.gl, "Paused", 20.0, HEIGHT as f32 * 0.5, yellow, 2.0);
+        let help = format!("press {} to carry on, {} controls", self.controls.keys(Action::PAUSE), self.controls.keys(Action::CONTROLS));
+        text.draw_text(&self.gl, &help, 20.0, HEIGHT as f32 * 0.5 - 50.0, yellow, 1.0);
+    }
 
-        let change = 70.0 * update_delta;
-        let steer_by = 1.5 * update_delta;
-        if self.left { self.player_avitar.steer_rotation_y_constant(-steer_by) } else if self.right { self.player_avitar.steer_rotation_y_constant(steer_by) } else {
-            //self.player_avitar.rotation_y_constant(0.0)
+    fn render_game_over(&self) {
+        let world = self.world.as_ref().unwrap();
+        if let Some(mission) = &world.mission {
+            self.draw_mission_results(mission, world.score, world.lives);
+        } else {
+            let message = if world.level_complete { "Well done, every level complete" } else { "Game over..." };
+            self.draw_game_over(message, world.score);
+        }
+        if self.name_entry.is_none() && self.replay.is_none() {
+            let text = self.draw_text.as_ref().unwrap();
+            let help = format!("press {} to play again, {} high scores", self.controls.keys(Action::START), self.controls.keys(Action::HIGHSCORES));
+            text.draw_text(&self.gl, &help, 20.0, 40.0, vec3(1.0, 1.0, 0.0), 1.0);
         }
+    }
+
+    fn render_high_scores(&self) {
+        let text = self.draw_text.as_ref().unwrap();
+        let yellow = vec3(1.0, 1.0, 0.0);
+        text.draw_text(&self.gl, "High scores", 20.0, HEIGHT as f32 - 60.0, yellow, 2.0);
+        let play = if self.world_over() { "play again" } else { "play" };
+        let play = format!("press {} to {}", self.controls.keys(Action::START), play);
+        text.draw_text(&self.gl, &play, 20.0, HEIGHT as f32 - 110.0, yellow, 1.0);
+        self.draw_high_scores(HEIGHT as f32 - 160.0);
+    }
 
-        if self.up {
-            self.player_avitar.go_forward(change, &self.ground.as_mut().unwrap())
-        } else if self.down {
-            self.player_avitar.go_forward(-change, &self.ground.as_mut().unwrap())
+    /// Every action and its keys, the picked one in white.
+    fn render_controls(&self) {
+        let text = self.draw_text.as_ref().unwrap();
+        let yellow = vec3(1.0, 1.0, 0.0);
+        text.draw_text(&self.gl, "Controls", 20.0, HEIGHT as f32 - 60.0, yellow, 2.0);
+        let help = if self.rebinding { "press the new key, escape to leave it" } else { "return change, backspace clear, escape done" };
+        text.draw_text(&self.gl, help, 20.0, HEIGHT as f32 - 110.0, yellow, 1.0);
+        for (row, action) in ACTIONS.iter().enumerate() {
+            let keys = if self.rebinding && row == self.controls_selected { String::from("?") } else { self.controls.keys(*action) };
+            let line = format!("{:<14} {}", action.describe(), keys);
+            let colour = if row == self.controls_selected { vec3(1.0, 1.0, 1.0) } else { yellow };
+            text.draw_text(&self.gl, &line, 20.0, HEIGHT as f32 - 160.0 - row as f32 * 27.0, colour, 1.0);
         }
+    }
 
+    /// Quitting in the middle of a game saves it to carry on with --load. Replays, games
+    /// that are already over and ones not started yet aren't worth keeping.
+    #[cfg(not(target_os = "emscripten"))]
+    fn save_on_quit(&self) {
+        let world = match &self.world {
+            Some(world) => world,
+            None => return,
+        };
+        let paused = self.state == GameState::PAUSED || (self.state == GameState::CONTROLS && self.controls_back == GameState::PAUSED);
+        if self.replay.is_some() || (self.state != GameState::PLAYING && !paused) {
+            return;
+        }
+        let filename = default_save_file();
+        match world.save_game(self.seconds_played).write(&filename) {
+            Ok(()) => println!("Saved, carry on with --load {}", filename),
+            Err(e) => println!("{}", e),
+        }
+    }
 
-        let accelerate_by = 0.25 * update_delta;
-        let slow_down = -0.05 * update_delta;
+    /// Anything good enough for the high scores gets a name typed in. Replays don't count,
+    /// they have been played before, and nor do missions.
+    fn game_over(&mut self) {
+        self.state = GameState::GAMEOVER;
+        self.input = Input::default();
+        let world = self.world.as_ref().unwrap();
+        if self.replay.is_some() {
+            println!("Replay over tick={} score={} lives={}", world.tick, world.score, world.lives);
+            return;
+        }
+        if world.mission.is_none() && self.high_scores.qualifies(world.score) {
+            self.name_entry = Some(String::new());
+            self.video.text_input().start();
+        }
+    }
 
-        if self.forward {
-            self.player_avitar.accelerate(accelerate_by, &self.ground.as_ref().unwrap());
+    /// A new game from the first level. Only the ground is read in again, and only if a
+    /// later level had been reached, the models and text stay as they are.
+    fn play_again(&mut self) {
+        let world = self.world.as_mut().unwrap();
+        let seed = world.rng.gen();
+        let mission = world.mission.as_ref().map(|m| m.mission.clone());
+        if self.level == self.first_level {
+            world.restart(seed);
         } else {
-            if self.player_avitar.accelerator_pressed <= 0.0 {
-                //stop(ENGINE);
-            }
-            self.player_avitar.accelerate(slow_down, &self.ground.as_ref().unwrap());
+            self.level = self.first_level.clone();
+            let world = World::new(seed, &self.level);
+            self.world_renderer.as_mut().unwrap().load_landscape(&self.gl, &world);
+            self.map_display = MapDisplay::new(&self.gl, &self.level.map);
+            self.world = Some(world);
         }
-        if self.space {
-            self.player_avitar.accelerate(slow_down * 6.0, &self.ground.as_ref().unwrap());
+        // a recording only follows one game, it would go wrong from here
+        if let Some(mut recorder) = self.recorder.take() {
+            recorder.finish().expect("could not finish recording");
+            println!("Recording stopped at the end of the first game");
         }
 
+        self.flash_message.clear();
+        if let Some(mission) = mission {
+            self.flash_message.push(mission.description.clone());
+            self.world.as_mut().unwrap().start_mission(mission);
+        }
+        self.flash_message.push(format!("level {}", self.level.name));
+        self.flash_message_countdown = 2.5;
+        self.input = Input::default();
+        self.accumulator = 0.0;
+        self.seconds_played = 0.0;
+        self.high_score_place = None;
+        self.state = GameState::PLAYING;
+    }
 
-        let end_status = self.handle_keyboard();
+    /// Return pressed after typing a name, the table is shown with it in.
+    fn enter_high_score(&mut self) {
+        self.video.text_input().stop();
+        let mut name = self.name_entry.take().unwrap_or_default().trim().to_string();
+        if name.is_empty() {
+            name = String::from("anon");
+        }
+        let world = self.world.as_ref().unwrap();
+        let high_score = HighScore {
+            name,
+            score: world.score,
+            level: world.level.name.clone(),
+            date: today(),
+            seconds: self.seconds_played,
+        };
+        self.high_score_place = self.high_scores.add(high_score);
+        self.high_scores.save();
+        self.state = GameState::HIGHSCORES;
+    }
 
-        /*
-                let mut list: Vec<String> = Vec::new();
-                list.push(format!("level {} score {} lives {} {}", self.level, self.score, self.lives, self.player_avitar.msg));
-                let update: String = list.join("\n");
+    fn draw_game_over(&self, message: &str, score: i32) {
+        let text = self.draw_text.as_ref().unwrap();
+        let yellow = vec3(1.0, 1.0, 0.0);
+        text.draw_text(&self.gl, message, 20.0, HEIGHT as f32 - 60.0, yellow, 2.0);
+        text.draw_text(&self.gl, &format!("score={}", score), 20.0, HEIGHT as f32 - 110.0, yellow, 1.5);
+        if let Some(name) = &self.name_entry {
+            text.draw_text(&self.gl, "a high score! type your name and press return", 20.0, HEIGHT as f32 * 0.5, yellow, 1.0);
+            text.draw_text(&self.gl, &format!("{}_", name), 20.0, HEIGHT as f32 * 0.5 - 50.0, yellow, 1.5);
+        }
+    }
 
-                #[allow(temporary_cstring_as_ptr)]
-                    write_stats_data(CString::new(update).to_owned().unwrap().as_ptr());
-        */
+    /// The table, one line each with the game just played in white.
+    fn draw_high_scores(&self, top: f32) {
+        let text = self.draw_text.as_ref().unwrap();
+        if self.high_scores.entry.is_empty() {
+            text.draw_text(&self.gl, "no high scores yet", 20.0, top, vec3(1.0, 1.0, 0.0), 1.0);
+            return;
+        }
+        let row_height = (top - 10.0) / MAX_HIGH_SCORES as f32;
+        for (place, entry) in self.high_scores.entry.iter().enumerate() {
+            let level: String = entry.level.chars().take(10).collect();
+            let line = format!("{:>2} {:<12} {:>6} {:<10} {} {}", place + 1, entry.name, entry.score, level, entry.date, clock(entry.seconds));
+            let colour = if self.high_score_place == Some(place) { vec3(1.0, 1.0, 1.0) } else { vec3(1.0, 1.0, 0.0) };
+            text.draw_text(&self.gl, &line, 0.0, top - place as f32 * row_height, colour, 0.75);
+        }
+    }
 
-        //output_elapsed(_debug_start, "time elapsed for gameloop");
-        end_status
+    /// The results screen at the end of a mission, or if the lives ran out first.
+    fn draw_mission_results(&self, mission: &MissionProgress, score: i32, lives: i32) {
+        let result = match &mission.state {
+            MissionState::SUCCEEDED => String::from("Mission complete"),
+            MissionState::FAILED(reason) => format!("Mission failed, {}", reason),
+            // the world stopped for some other reason first
+            MissionState::RUNNING => String::from("Mission failed, out of lives"),
+        };
+        let lines = [
+            mission.mission.name.clone(),
+            mission.mission.description.clone(),
+            result,
+            mission.progress(),
+            format!("time {} score {} lives {}", clock(mission.seconds), score, lives),
+        ];
+        let text = self.draw_text.as_ref().unwrap();
+        for (row, line) in lines.iter().enumerate() {
+            let scale = if row == 2 { 2.0 } else { 1.0 };
+            text.draw_text(&self.gl, line, 40.0, HEIGHT as f32 * 0.75 - row as f32 * 50.0, vec3(1.0, 1.0, 0.0), scale);
+        }
     }
 
-    fn add_some_passengers_if_required(&mut self, humans: usize) {
-        if self.ground.is_some() {
-            if (self.passengers.len() == 0 || humans <= 3) && self.passengers.len() <= MAX_PASSENGERS {
-                //let original_instances = self.create_passenger();
-
-                let just_one = false;
-                if just_one {
-                    let mut passenger = Passenger::new(&self.gl, vec3(0.0, 0.20, -2.6), &self.original_passenger_to_copy);
-                    passenger.set_random_time_to_zombie();
-                    self.passengers.push(passenger);
-                }
-
-                if !just_one {
-                    let mut rng = rand::thread_rng();
-
-                    for r in self.ground.as_ref().unwrap().land.iter() {
-                        for l in r.iter() {
-                            if rng.gen_range(0, 20) < 5 {
-                                for landscape_object in l.landscape_objects.iter() {
-                                    if landscape_object.description.starts_with("road") && self.passengers.len() <= MAX_PASSENGERS {
-                                        let mut start_position = vec3(0.0, 0.0, 0.0);
-                                        for v in landscape_object.vertices.iter() {
-                                            start_position = start_position.add(v);
-                                        }
-                                        start_position = start_position.div(landscape_object.vertices.len() as f32);
-                                        start_position = start_position + l.xyz;
-                                        start_position.y = 0.2;
-                                        let mut passenger = Passenger::new(&self.gl, start_position, &self.original_passenger_to_copy.clone());
-                                        passenger.set_random_time_to_zombie();
-                                        self.passengers.push(passenger)
-                                    }
-                                }
-                            }
-                        }
+    fn handle_world_events(&mut self) {
+        let mut level_complete = false;
+        for event in self.world.as_ref().unwrap().events.iter() {
+            match event {
+                WorldEvent::PassengerPickedUp => {
+                    play(SCOOP);
+                    self.flash_message.push(String::from("passenger picked up"));
+                    self.flash_message_countdown = 1.5;
+                }
+                WorldEvent::PassengerDelivered { score } => {
+                    play(SCOOP);
+                    self.flash_message.push(format!("passenger delivered +{}", score));
+                    self.flash_message_countdown = 1.5;
+                }
+                WorldEvent::RiderTurning => {
+                    play(WARNING);
+                    self.flash_message.push(String::from("a rider is turning, stop the bus!"));
+                    self.flash_message_countdown = 2.5;
+                }
+                WorldEvent::RiderEjected => {
+                    self.flash_message.push(String::from("zombie thrown off the bus"));
+                    self.flash_message_countdown = 2.5;
+                }
+                WorldEvent::RiderTurned => {
+                    play(EXPLOSION);
+                    self.flash_message.push(String::from("a rider turned zombie on the bus"));
+                    self.flash_message_countdown = 2.5;
+                }
+                WorldEvent::RiderCured => {
+                    play(SCOOP);
+                    self.flash_message.push(String::from("rider cured"));
+                    self.flash_message_countdown = 1.5;
+                }
+                WorldEvent::ZombieExploded => {
+                    self.flash_message.push(String::from("zombie exploded near you"));
+                    self.flash_message_countdown = 2.5;
+                }
+                WorldEvent::ZombieRunOver => {
+                    play(EXPLOSION);
+                    self.flash_message.push(String::from("zombie run over"));
+                    self.flash_message_countdown = 1.5;
+                }
+                WorldEvent::BusWrecked => {
+                    play(EXPLOSION);
+                    self.flash_message.push(String::from("bus wrecked"));
+                    self.flash_message_countdown = 2.5;
+                }
+                WorldEvent::BusHitScenery { scenery_type, impact_speed } => {
+                    play(EXPLOSION);
+                    let what = match scenery_type {
+                        SceneryType::TREE => "tree",
+                        SceneryType::HOUSE => "house",
+                        SceneryType::OFFICE1 => "office",
+                        SceneryType::HOSPITAL => "hospital",
+                    };
+                    if *impact_speed > 1.0 {
+                        self.flash_message.push(format!("crashed into a {}", what));
+                    } else {
+                        self.flash_message.push(format!("scraped a {}", what));
                     }
+                    self.flash_message_countdown = 1.5;
+                }
+                WorldEvent::BusHitCar { impact_speed } => {
+                    play(EXPLOSION);
+                    if *impact_speed > 1.0 {
+                        self.flash_message.push(String::from("crashed into a car"));
+                    } else {
+                        self.flash_message.push(String::from("bumped a car"));
+                    }
+                    self.flash_message_countdown = 1.5;
+                }
+                WorldEvent::HumanHitByCar => {
+                    self.flash_message.push(String::from("a car knocked someone down"));
+                    self.flash_message_countdown = 1.5;
+                }
+                WorldEvent::ZombieSpat => {
+                    play(WARNING);
+                    self.flash_message.push(String::from("spat on by a zombie"));
+                    self.flash_message_countdown = 2.5;
+                }
+                WorldEvent::OffRoadWarning => {
+                    play(WARNING);
+                }
+                WorldEvent::OffRoadTooLong => {
+                    play(EXPLOSION);
+                    self.flash_message.push(String::from("off road too long"));
+                    self.flash_message_countdown = 2.5;
+                }
+                WorldEvent::WaveStarted { wave } => {
+                    play(WARNING);
+                    self.flash_message.push(format!("wave {}", wave));
+                    self.flash_message_countdown = 2.5;
+                }
+                WorldEvent::WaveCleared { wave } => {
+                    play(SCOOP);
+                    self.flash_message.push(format!("wave {} cleared", wave));
+                    self.flash_message_countdown = 2.5;
+                }
+                WorldEvent::MissionSucceeded => {
+                    play(SCOOP);
+                    self.flash_message.push(String::from("mission complete"));
+                    self.flash_message_countdown = 2.5;
+                }
+                WorldEvent::MissionFailed { reason } => {
+                    play(EXPLOSION);
+                    self.flash_message.push(format!("mission failed, {}", reason));
+                    self.flash_message_countdown = 2.5;
+                }
+                WorldEvent::LevelComplete => {
+                    level_complete = true;
                 }
-                //println!("Added some passengers!!!");
             }
         }
+        if level_complete {
+            self.start_next_level();
+        }
     }
 
-    fn create_passenger(&self) -> Vec<ModelInstance> {
-        let model_zero = Model::new(&self.gl, "resources/models/man0.obj", "resources/models/body.png");
-        let model_zero_instance = ModelInstance::new(&self.gl, model_zero.clone(), PASSENGER_SCALE, Some("resources/models/zombie.png"));
-        let mut instances = Vec::<ModelInstance>::new();
-        for i in 1..3 {
-            let name = format!("resources/models/man{}.obj", i);
-            println!("Load {}", name);
-            let model = Model::new(&self.gl, name.as_str(), "resources/models/body.png");
-            let model_instance = ModelInstance::new(&self.gl, model.clone(), PASSENGER_SCALE, Some("resources/models/zombie.png"));
-            instances.push(model_zero_instance.clone());
-            instances.push(model_instance);
-        }
-        return instances;
+    /// Swap in a fresh world for the level after this one, the score carries on.
+    /// With no next level the finished world is left in place and shown as won.
+    fn start_next_level(&mut self) {
+        let world = self.world.as_mut().unwrap();
+        let next = match world.level.next.clone() {
+            Some(next) => next,
+            None => return,
+        };
+        self.level = Level::load(&next).unwrap_or_else(|e| panic!("{}", e));
+        // seed from the old world so a replay still follows the same game
+        let seed = world.rng.gen();
+        let mut next_world = World::new(seed, &self.level);
+        next_world.score = world.score;
+
+        self.world_renderer.as_mut().unwrap().load_landscape(&self.gl, &next_world);
+        self.map_display = MapDisplay::new(&self.gl, &self.level.map);
+        self.world = Some(next_world);
+        self.flash_message.push(format!("level {}", self.level.name));
+        self.flash_message_countdown = 2.5;
     }
 
     /*
@@ -1,40 +1,46 @@
-use std::collections::HashMap;
+use cgmath::{InnerSpace, vec3, Vector3};
 
-use cgmath::{Matrix4, vec2, vec3, Vector2, Vector3};
-
-use crate::{get_start_time, gl, output_elapsed};
-//use crate::gl_helper::model::Model;
+use crate::{get_start_time, output_elapsed};
+use crate::bus_stop::BusStop;
 use crate::landscape::{AtCell, Landscape, LandscapeObject, SQUARE_COLUMNS, SQUARE_ROWS, SQUARE_SIZE};
-use crate::passengers::Passenger;
-use crate::scenery::Scenery;
+use crate::map_manifest::MapManifest;
+use crate::road_graph::RoadGraph;
+use crate::scenery::{Scenery, SceneryType};
+use crate::spatial_grid::SpatialGrid;
 
 pub struct Ground {
     pub land: Vec<Vec<Landscape>>,
     pub player_pos: Vector3<f32>,
-    to_display: HashMap<Vector2<i32>, Vector2<i32>>,
+    /// tiles along each side of the city, from the map manifest
+    pub by: usize,
+    pub roads: RoadGraph,
+    /// every tree and building, ids index scenery_places
+    scenery_grid: SpatialGrid,
+    /// row, column and index into scenery_instances of each tree and building
+    scenery_places: Vec<(usize, usize, usize)>,
+    /// biggest collision_radius of any scenery
+    scenery_reach: f32,
 }
 
-pub(crate) const BY: usize = 5;
+// squares of the spatial grids along the side of each tile
+pub const GRID_SQUARES_PER_TILE: usize = 4;
 
 impl Ground {
     pub(crate) const MUL: f32 = SQUARE_SIZE * SQUARE_COLUMNS as f32;
 
-    pub fn new(gl: &gl::Gl) -> Ground {
+    pub fn new(manifest: &MapManifest) -> Ground {
         let start = get_start_time();
+        let by = manifest.size;
         let mut land: Vec<Vec<Landscape>> = vec![vec![]];
-        let height_map: Vec<Vec<AtCell>> = vec![vec![AtCell { height: 0.0 }; SQUARE_COLUMNS * BY]; SQUARE_COLUMNS * BY];
-
-        let offset_x = BY as f32 * Ground::MUL * 0.5 - SQUARE_COLUMNS as f32 * SQUARE_SIZE * 0.5;
-        let offset_z = BY as f32 * Ground::MUL * 0.5 - SQUARE_ROWS as f32 * SQUARE_SIZE * 0.5;
+        let height_map: Vec<Vec<AtCell>> = vec![vec![AtCell { height: 0.0 }; SQUARE_COLUMNS * by]; SQUARE_COLUMNS * by];
 
-        let tree_model = Scenery::setup_tree(&gl);
-        let house_model = Scenery::setup_house(&gl);
-        let office1_model = Scenery::setup_office1(&gl);
+        let offset_x = by as f32 * Ground::MUL * 0.5 - SQUARE_COLUMNS as f32 * SQUARE_SIZE * 0.5;
+        let offset_z = by as f32 * Ground::MUL * 0.5 - SQUARE_ROWS as f32 * SQUARE_SIZE * 0.5;
 
-        for y in 0..BY {
+        for y in 0..by {
             land.push(vec![]);
 
-            for x in 0..BY {
+            for x in 0..by {
                 let mut cell_height_map: Vec<Vec<AtCell>> = vec![vec![AtCell { height: 7.9 }; SQUARE_COLUMNS]; SQUARE_COLUMNS];
                 for cell_y in 0..SQUARE_ROWS {
                     for cell_x in 0..SQUARE_COLUMNS {
@@ -45,20 +51,43 @@ impl Ground {
                     }
                 }
                 let here = vec3(x as f32 * Ground::MUL - offset_x, 0.0, y as f32 * Ground::MUL - offset_z);
-                let land_cell = Landscape::new(&gl, "resources/ground.png", here, format!("{}_{}", x, y), &mut cell_height_map,
-                                               &tree_model, &house_model, &office1_model);
+                let land_cell = Landscape::new(here, manifest.tile_filename(x, y), &mut cell_height_map);
 
                 land[y].push(land_cell);
                 assert_eq!(land[y][x].xyz, here);
             }
         }
 
-        output_elapsed(start, "Time elapsed in expensive_ground() is");
+        let roads = RoadGraph::new(&land, Ground::MUL * by as f32);
 
-        Ground {
+        let mut ground = Ground {
             land,
             player_pos: vec3(0.0, 0.0, 0.0),
-            to_display: HashMap::new(),
+            by,
+            roads,
+            scenery_grid: SpatialGrid::new(Ground::MUL * by as f32, by * GRID_SQUARES_PER_TILE),
+            scenery_places: vec![],
+            scenery_reach: 0.0,
+        };
+        ground.index_scenery();
+
+        output_elapsed(start, "Time elapsed in expensive_ground() is");
+
+        ground
+    }
+
+    fn index_scenery(&mut self) {
+        self.scenery_grid.rebuild(std::iter::empty());
+        self.scenery_places.clear();
+        self.scenery_reach = 0.0;
+        for (zz, row) in self.land.iter().enumerate() {
+            for (xx, landscape) in row.iter().enumerate() {
+                for (index, scenery) in landscape.scenery_instances.iter().enumerate() {
+                    self.scenery_grid.insert(landscape.xyz + scenery.position);
+                    self.scenery_places.push((zz, xx, index));
+                    self.scenery_reach = self.scenery_reach.max(scenery.collision_radius);
+                }
+            }
         }
     }
 
@@ -68,117 +97,161 @@ impl Ground {
         self.player_pos.z = z;
     }
     pub fn currently_under_landscape(&self, x: f32, z: f32) -> &Landscape {
-        let (xx, zz) = Ground::get_current_cell(x, z);
+        let (xx, zz) = self.get_current_cell(x, z);
         &self.land[zz][xx]
     }
+    /// x and z can be just over the edge of the world, they are wrapped round first.
     pub fn object_at(&self, x: f32, z: f32) -> Option<&LandscapeObject> {
-        let (xx, zz) = Ground::get_current_cell(x, z);
-        return self.land[zz][xx].object_at(x, z);
+        let p = self.roads.wrap_position(vec3(x, 0.0, z));
+        let (xx, zz) = self.get_current_cell(p.x, p.z);
+        self.land[zz][xx].object_at(p.x, p.z)
     }
-    pub fn scenery_at(&self, x: f32, z: f32) -> (Option<&Scenery>,&Vector3<f32>) {
-        let (xx, zz) = Ground::get_current_cell(x, z);
-        return (self.land[zz][xx].scenery_at(x, z),&self.land[zz][xx].xyz);
+
+    /// Scenery that could be touching a circle of radius r at position, whichever tile it is on
+    /// and wrapping round the edge of the world. Each comes with where its tile is, moved by the
+    /// width of the world if that copy of the tile is the one next to position.
+    pub fn scenery_within(&self, position: Vector3<f32>, r: f32) -> Vec<(&Scenery, Vector3<f32>)> {
+        let position = self.roads.wrap_position(position);
+        let mut found: Vec<(&Scenery, Vector3<f32>)> = vec![];
+        for id in self.scenery_grid.within(position, r + self.scenery_reach) {
+            let (zz, xx, index) = self.scenery_places[id];
+            let landscape = &self.land[zz][xx];
+            let scenery = &landscape.scenery_instances[index];
+            let mut apart = self.roads.wrapped_delta(position, landscape.xyz + scenery.position);
+            apart.y = 0.0;
+            if apart.magnitude() < r + scenery.collision_radius {
+                let mut tile_xyz = position + apart - scenery.position;
+                tile_xyz.y = landscape.xyz.y;
+                found.push((scenery, tile_xyz));
+            }
+        }
+        found
     }
 
     pub fn position_height(&self, x: f32, z: f32) -> f32 {
-        let (xx, zz) = Ground::get_current_cell(x, z);
-        let height = self.land[zz][xx].position_height(x, z);
+        let p = self.roads.wrap_position(vec3(x, 0.0, z));
+        let (xx, zz) = self.get_current_cell(p.x, p.z);
+        let height = self.land[zz][xx].position_height(p.x, p.z);
 
         height
     }
 
-    pub(crate) fn get_current_cell(x: f32, z: f32) -> (usize, usize) {
-        let mut xx = ((x + Ground::MUL * BY as f32 * 0.5) / Ground::MUL) as usize;
-        let mut zz = ((z + Ground::MUL * BY as f32 * 0.5) / Ground::MUL) as usize;
-        if xx >= BY { xx = BY - 1 }
-        if zz >= BY { zz = BY - 1 }
-        (xx, zz)
-    }
-    pub fn update(&mut self, _gl: &gl::Gl, player_position: Vector3<f32>,  camera_angle: f32, _delta: f32) {
-        self.sort_out_what_to_display(player_position, camera_angle);
+    /// Every stop in the city with its position in the world.
+    pub fn bus_stops(&self) -> Vec<BusStop> {
+        let mut stops: Vec<BusStop> = vec![];
+        for row in self.land.iter() {
+            for landscape in row.iter() {
+                for stop in landscape.bus_stops.iter() {
+                    stops.push(BusStop::new(stop.name.clone(), landscape.xyz + stop.position));
+                }
+            }
+        }
+        stops
     }
 
-
-    fn sort_out_what_to_display(&mut self, player_position: Vector3<f32>, camera_angle: f32) {
-        self.to_display.clear();
-
-        let (current_xx, current_zz) = Ground::get_current_cell(player_position.x, player_position.z);
-
-        let xx = current_xx as f32;
-        let zz = current_zz as f32;
-
-        let v = vec2(xx as i32, zz as i32);
-        self.to_display.insert(v, v);
-
-        self.make_sure_everything_around_player_shown(xx, zz);
-
-        // -1 so make sure when low we still show cell
-        for going_away in -1..(BY as i32 * 2) {
-            for a in (-120..130).step_by(5) {
-                let apply = Vector3 {
-                    x: (a as f32 - camera_angle).to_radians().sin() * going_away as f32,
-                    y: 0.0,
-                    z: (a as f32 - camera_angle).to_radians().cos() * -going_away as f32,
-                };
-                let v = vec2((xx + apply.x) as i32, (zz + apply.z) as i32);
-                self.to_display.insert(v, v);
+    /// Where every hospital is in the world.
+    pub fn hospitals(&self) -> Vec<Vector3<f32>> {
+        let mut hospitals: Vec<Vector3<f32>> = vec![];
+        for row in self.land.iter() {
+            for landscape in row.iter() {
+                for scenery in landscape.scenery_instances.iter() {
+                    if scenery.scenery_type == SceneryType::HOSPITAL {
+                        hospitals.push(landscape.xyz + scenery.position);
+                    }
+                }
             }
         }
+        hospitals
     }
 
-    fn make_sure_everything_around_player_shown(&mut self, xx: f32, zz: f32) {
-        let v = vec2(xx as i32 + 1, zz as i32);
-        self.to_display.insert(v, v);
-        let v = vec2(xx as i32 + 1, zz as i32 + 1);
-        self.to_display.insert(v, v);
-        let v = vec2(xx as i32, zz as i32 + 1);
-        self.to_display.insert(v, v);
-        let v = vec2(xx as i32 - 1, zz as i32);
-        self.to_display.insert(v, v);
-        let v = vec2(xx as i32, zz as i32 - 1);
-        self.to_display.insert(v, v);
-        let v = vec2(xx as i32 - 1, zz as i32 - 1);
-        self.to_display.insert(v, v);
+    /// Distance across the whole city, going this far in any direction brings you back to the start.
+    pub fn width(&self) -> f32 {
+        Ground::MUL * self.by as f32
     }
 
+    pub(crate) fn get_current_cell(&self, x: f32, z: f32) -> (usize, usize) {
+        let by = self.by;
+        let mut xx = ((x + Ground::MUL * by as f32 * 0.5) / Ground::MUL) as usize;
+        let mut zz = ((z + Ground::MUL * by as f32 * 0.5) / Ground::MUL) as usize;
+        if xx >= by { xx = by - 1 }
+        if zz >= by { zz = by - 1 }
+        (xx, zz)
+    }
 
-    pub fn render(&mut self, gl: &gl::Gl, view: &Matrix4<f32>, projection: &Matrix4<f32>, _player_position: Vector3<f32>, _camera_angle: f32, our_shader: u32,
-                  passengers: &mut Vec<Passenger>,tick:i128) {
-        for xz in self.to_display.values() {
-            let yyy = wrap_value(xz.y);
-            let xxx = wrap_value(xz.x);
-            let offset = BY as f32 * Ground::MUL / 2.0 - Ground::MUL / 2.0;
-            let position = vec3(xz.x as f32 * (Ground::MUL) - offset, 0.0, xz.y as f32 * (Ground::MUL) - offset);
-            let here = Matrix4::<f32>::from_translation(position);
-            //print!("yyy={},xxx={}  here {},{}      {},{} ",yyy,xxx,position.x,position.z,self.land[yyy][xxx].xyz.x,self.land[yyy][xxx].xyz.z);
-            self.land[yyy as usize][xxx as usize].render(gl, view, projection, here, position, our_shader);
-
-            let land_xyz = self.land[yyy as usize][xxx as usize].xyz;
-
-            let avatar_offset = position - land_xyz;
-            for passenger in passengers.iter_mut() {
-                let (xx, zz) = Ground::get_current_cell(passenger.movement_collision.position.x, passenger.movement_collision.position.z);
-                if xx as i32 == xxx && zz as i32 == yyy {
-                    passenger.render(gl, &view, &projection, our_shader, avatar_offset,tick);
-                }
+    pub(crate) fn wrap_value(&self, v: i32) -> i32 {
+        let by = self.by as i32;
+        let mut r = v;
+        if r >= by {
+            while r >= by {
+                r -= by;
+            }
+        }
+        if r < 0 {
+            while r < 0 {
+                r += by;
             }
         }
+        if r < 0 { r = -99 };
+        if r >= by { r = -99 };
+        r
     }
 }
 
-fn wrap_value(v: i32) -> i32 {
-    let mut r = v;
-    if r >= BY as i32 {
-        while r >= BY as i32 {
-            r = r - BY as i32;
+#[cfg(test)]
+mod tests {
+    use crate::level::{DEFAULT_LEVEL, Level};
+
+    use super::*;
+
+    const RADIUS: f32 = 0.15;
+
+    /// The default map with no scenery but one tree, at offset from the middle of tile x, z.
+    fn ground_with_tree(x: usize, z: usize, offset: Vector3<f32>) -> Ground {
+        let mut ground = Ground::new(&Level::load(DEFAULT_LEVEL).unwrap().map);
+        for row in ground.land.iter_mut() {
+            for landscape in row.iter_mut() {
+                landscape.scenery_instances.clear();
+            }
         }
+        ground.land[z][x].scenery_instances.push(Scenery::new_tree(offset));
+        ground.index_scenery();
+        ground
     }
-    if r < 0 {
-        while r < 0 {
-            r = r + BY as i32;
-        }
+
+    #[test]
+    fn a_tree_just_over_a_tile_border_is_found_from_the_next_tile() {
+        let half = Ground::MUL / 2.0;
+        let ground = ground_with_tree(1, 0, vec3(0.05 - half, 0.0, 0.0));
+        let tree_at = ground.land[0][1].xyz + vec3(0.05 - half, 0.0, 0.0);
+        // on the tile to the west, 0.2 from the tree
+        let from = tree_at - vec3(0.2, 0.0, 0.0);
+        assert_eq!(ground.get_current_cell(from.x, from.z), (0, 0));
+        let found = ground.scenery_within(from, RADIUS);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].1, ground.land[0][1].xyz);
+        // pushes back west, away from the tree
+        let push = found[0].0.push_out(found[0].1, from, RADIUS).unwrap();
+        assert!(push.x < 0.0 && push.z.abs() < 0.0001);
+
+        assert!(ground.scenery_within(tree_at - vec3(0.4, 0.0, 0.0), RADIUS).is_empty());
     }
-    if r < 0 { r = -99 };
-    if r >= BY as i32 { r = -99 };
-    return r;
-}
\ No newline at end of file
+
+    #[test]
+    fn a_tree_over_the_edge_of_the_world_is_found() {
+        let half = Ground::MUL / 2.0;
+        // the default map is 5 tiles across
+        let last = 4;
+        let ground = ground_with_tree(last, 0, vec3(half - 0.05, 0.0, 0.0));
+        let width = ground.width();
+        let tree_at = ground.land[0][last].xyz + vec3(half - 0.05, 0.0, 0.0);
+        assert!((tree_at.x - width / 2.0).abs() < 0.1);
+        // just inside the west edge of the world, 0.15 from the tree the short way round
+        let from = tree_at + vec3(0.15 - width, 0.0, 0.0);
+        let found = ground.scenery_within(from, RADIUS);
+        assert_eq!(found.len(), 1);
+        // the copy of the tile over to the west of the world
+        assert!((found[0].1 - (ground.land[0][last].xyz - vec3(width, 0.0, 0.0))).magnitude() < 0.0001);
+        let push = found[0].0.push_out(found[0].1, from, RADIUS).unwrap();
+        assert!(push.x > 0.0 && push.z.abs() < 0.0001);
+    }
+}
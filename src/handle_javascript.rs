@@ -39,3 +39,63 @@ extern "C" {
     pub fn end_game() -> i32;
 }
 
+
+// high scores live in the browser's local storage, passed through here as toml
+// with a NUL on the end so javascript can read it as a C string
+#[cfg(target_os = "emscripten")]
+lazy_static! {
+    static ref HIGH_SCORES: Mutex<String> = Mutex::new(String::from("\0"));
+}
+
+#[cfg(target_os = "emscripten")]
+#[no_mangle]
+pub extern "C" fn javascript_write_high_scores(input: *const c_char) {
+    unsafe {
+        let rust = CStr::from_ptr(input);
+        let mut data = HIGH_SCORES.lock().unwrap();
+        data.clear();
+        data.push_str(rust.to_str().unwrap_or(""));
+        data.push(char::from(0));
+    }
+}
+
+#[cfg(target_os = "emscripten")]
+#[no_mangle]
+pub extern "C" fn javascript_read_high_scores() -> *const c_char {
+    let data = HIGH_SCORES.lock().unwrap();
+    data.as_ptr() as *const c_char
+}
+
+#[cfg(target_os = "emscripten")]
+extern "C" {
+    pub fn load_high_scores() -> i32;
+}
+
+#[cfg(target_os = "emscripten")]
+extern "C" {
+    pub fn save_high_scores() -> i32;
+}
+
+/// Asks the page for what it has in local storage.
+#[cfg(target_os = "emscripten")]
+pub fn read_high_scores() -> Option<String> {
+    unsafe {
+        load_high_scores();
+    }
+    let data = HIGH_SCORES.lock().unwrap();
+    let text = data.trim_end_matches(char::from(0));
+    if text.is_empty() { None } else { Some(text.to_string()) }
+}
+
+#[cfg(target_os = "emscripten")]
+pub fn write_high_scores(text: &str) {
+    {
+        let mut data = HIGH_SCORES.lock().unwrap();
+        data.clear();
+        data.push_str(text);
+        data.push(char::from(0));
+    }
+    unsafe {
+        save_high_scores();
+    }
+}
@@ -0,0 +1,180 @@
+#[cfg(not(target_os = "emscripten"))]
+use std::fs;
+#[cfg(not(target_os = "emscripten"))]
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+pub const MAX_HIGH_SCORES: usize = 20;
+// letters in a name, the table is only 50 letters across
+pub const MAX_NAME: usize = 12;
+#[cfg(not(target_os = "emscripten"))]
+const HIGH_SCORES_FILE: &str = "high_scores.toml";
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct HighScore {
+    pub name: String,
+    pub score: i32,
+    /// name of the level the game ended on
+    pub level: String,
+    /// year-month-day
+    pub date: String,
+    /// time spent driving, not counting loading or the game over screen
+    pub seconds: f32,
+}
+
+/// The best MAX_HIGH_SCORES games, best first. A toml file on desktop, local storage in a browser.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+pub struct HighScores {
+    #[serde(default)]
+    pub entry: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// Nothing saved yet, or something that can't be read, is an empty table.
+    pub fn load() -> HighScores {
+        let text = match read_saved() {
+            Some(text) => text,
+            None => return HighScores::default(),
+        };
+        toml::from_str(&text).unwrap_or_else(|e| {
+            println!("Ignoring high scores that could not be read: {}", e);
+            HighScores::default()
+        })
+    }
+
+    pub fn save(&self) {
+        match toml::to_string(self) {
+            Ok(text) => write_saved(&text),
+            Err(e) => println!("Could not save high scores: {}", e),
+        }
+    }
+
+    pub fn qualifies(&self, score: i32) -> bool {
+        score > 0 && (self.entry.len() < MAX_HIGH_SCORES || score > self.entry.last().map_or(0, |e| e.score))
+    }
+
+    /// Goes in after anyone with the same score, Some(place) counting from 0 if it made the table.
+    pub fn add(&mut self, high_score: HighScore) -> Option<usize> {
+        let place = self.entry.iter().position(|e| e.score < high_score.score).unwrap_or(self.entry.len());
+        if place >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.entry.insert(place, high_score);
+        self.entry.truncate(MAX_HIGH_SCORES);
+        Some(place)
+    }
+}
+
+/// Today as year-month-day, worked out from the days since 1970.
+pub fn today() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() / 86400) as i64;
+    // civil from days, March based years so the leap day comes last
+    let z = days + 719468;
+    let era = z / 146097;
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+/// Where the game keeps anything it saves, ~/.local/share/bus-zombie-rust on Linux,
+/// Application Support on a Mac and AppData\Roaming on Windows.
+#[cfg(not(target_os = "emscripten"))]
+pub fn data_dir() -> PathBuf {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home.map(|h| h.join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME").map(PathBuf::from).or(home.map(|h| h.join(".local").join("share")))
+    };
+    base.unwrap_or(PathBuf::from(".")).join("bus-zombie-rust")
+}
+
+#[cfg(not(target_os = "emscripten"))]
+fn read_saved() -> Option<String> {
+    fs::read_to_string(data_dir().join(HIGH_SCORES_FILE)).ok()
+}
+
+#[cfg(not(target_os = "emscripten"))]
+fn write_saved(text: &str) {
+    let dir = data_dir();
+    let written = fs::create_dir_all(&dir).and_then(|_| fs::write(dir.join(HIGH_SCORES_FILE), text));
+    if let Err(e) = written {
+        println!("Could not save high scores to {}: {}", dir.display(), e);
+    }
+}
+
+#[cfg(target_os = "emscripten")]
+fn read_saved() -> Option<String> {
+    crate::handle_javascript::read_high_scores()
+}
+
+#[cfg(target_os = "emscripten")]
+fn write_saved(text: &str) {
+    crate::handle_javascript::write_high_scores(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn high_score(name: &str, score: i32) -> HighScore {
+        HighScore { name: name.to_string(), score, level: String::from("level1"), date: String::from("2020-01-01"), seconds: 60.0 }
+    }
+
+    /// A full table, 20 down to 1 points per place.
+    fn full_table() -> HighScores {
+        let mut high_scores = HighScores::default();
+        for score in (1..=MAX_HIGH_SCORES as i32).rev() {
+            high_scores.add(high_score("full", score));
+        }
+        high_scores
+    }
+
+    #[test]
+    fn a_zero_score_never_qualifies() {
+        assert!(!HighScores::default().qualifies(0));
+        assert!(!HighScores::default().qualifies(-5));
+        assert!(HighScores::default().qualifies(1));
+    }
+
+    #[test]
+    fn the_table_keeps_the_best_twenty() {
+        let mut high_scores = full_table();
+        assert_eq!(high_scores.entry.len(), MAX_HIGH_SCORES);
+        assert!(!high_scores.qualifies(1));
+        assert!(high_scores.qualifies(2));
+
+        assert_eq!(high_scores.add(high_score("new", 10)), Some(11));
+        assert_eq!(high_scores.entry.len(), MAX_HIGH_SCORES);
+        assert_eq!(high_scores.entry.last().unwrap().score, 2);
+        let scores: Vec<i32> = high_scores.entry.iter().map(|e| e.score).collect();
+        assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn last_place_on_a_full_table_does_not_go_in() {
+        let mut high_scores = full_table();
+        assert_eq!(high_scores.add(high_score("new", 1)), None);
+        assert_eq!(high_scores.entry, full_table().entry);
+    }
+
+    #[test]
+    fn an_equal_score_goes_in_after_the_one_already_there() {
+        let mut high_scores = HighScores::default();
+        high_scores.add(high_score("first", 100));
+        high_scores.add(high_score("low", 50));
+        assert_eq!(high_scores.add(high_score("second", 100)), Some(1));
+        assert_eq!(high_scores.add(high_score("third", 100)), Some(2));
+        let names: Vec<&str> = high_scores.entry.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["first", "second", "third", "low"]);
+    }
+}
@@ -1,19 +1,11 @@
 extern crate cgmath;
 
-use std::{mem};
-use std::os::raw::c_void;
-use std::ptr;
-
 use cgmath::*;
-use csv::{StringRecord, Trim};
 
-use crate::gl;
-use crate::gl_helper::gl_matrix4;
-use crate::gl_helper::texture::{create_texture_jpg, create_texture_png};
+use crate::bus_stop::BusStop;
 use crate::scenery::Scenery;
-use crate::gl_helper::model::Model;
-
-static mut TEXTURE_LOADED: i32 = -1;
+pub use crate::tile_file::IMAGE_SCALE_FACTOR;
+use crate::tile_file::{check_tile, parse_tile, TileVertex};
 
 pub struct LandscapeInstance {
     pub id: u128,
@@ -30,7 +22,6 @@ pub const SQUARE_SIZE: f32 = 0.2;
 pub const SQUARE_ROWS: usize = 32;
 pub const SQUARE_COLUMNS: usize = 32;
 //pub const MAX: f32 = 0.12;
-pub const IMAGE_SCALE_FACTOR: f32 = 256.0;
 
 #[derive(Clone)]
 pub struct AtCell {
@@ -45,14 +36,15 @@ pub struct LandscapeObject {
 
 pub struct Landscape {
     //id:u128,
-    texture: u32,
-    vao: u32,
     height_map: Vec<Vec<AtCell>>,
     pub xyz: Vector3<f32>,
-    vertices_count: usize,
+    pub vertices: Vec<f32>,
+    pub vertices_count: usize,
     pub filename: String,
     pub landscape_objects: Vec<LandscapeObject>,
     pub scenery_instances: Vec<Scenery>,
+    /// positions are from the middle of the tile like the scenery
+    pub bus_stops: Vec<BusStop>,
 }
 
 
@@ -60,15 +52,15 @@ pub struct Landscape {
 //pub const MAX_HEIGHT: f32 = 2.5;
 
 impl Landscape {
-    pub fn new(gl: &gl::Gl, image_file: &str, xyz: Vector3<f32>, name: String, height_map: &mut Vec<Vec<AtCell>>,tree_model:&Model,house_model:&Model,office1_model:&Model) -> Landscape {
-        let filename = format!("resources/road_{}.txt", name);
+    pub fn new(xyz: Vector3<f32>, filename: String, height_map: &mut [Vec<AtCell>]) -> Landscape {
         let mut landscape_objects: Vec<LandscapeObject> = vec![];
         let mut scenery_instances: Vec<Scenery> = vec![];
+        let mut bus_stops: Vec<BusStop> = vec![];
 
         let split_up = 4;
         let grass_min=0.75;
         let grass = SQUARE_SIZE * SQUARE_ROWS as f32 / split_up as f32 ;
-        let (_vbo, vao, texture, vertices_count) = unsafe {
+        let (vertices, vertices_count) = {
 
             let mut vertices: Vec<f32> = vec![
             ];
@@ -90,109 +82,80 @@ impl Landscape {
             }
 
 
-            println!("-----------------------    {}       {}/{}", name, xyz.x, xyz.z);
-            let mut rows: Vec<StringRecord> = vec![];
-            let reader = csv::ReaderBuilder::new().has_headers(false).flexible(true)
-                .comment(Some(b'#')).trim(Trim::All).from_path(&filename).expect(&filename);
-            let mut landscape_object: LandscapeObject = LandscapeObject {
-                vertices: vec![],
-                description: String::new(),
-            };
-            for record in reader.into_records() {
-                if record.is_ok() {
-                    let record = record.unwrap();
-                    if (&record[0]).starts_with("o") {
-                        let what = &record[1];
-                        let x = &record[2].parse::<f32>().unwrap() * SQUARE_SIZE - SQUARE_SIZE * SQUARE_COLUMNS as f32 / 2.0;
-                        let y = &record[3].parse::<f32>().unwrap() * SQUARE_SIZE ;
-                        let z = &record[4].parse::<f32>().unwrap() * SQUARE_SIZE - SQUARE_SIZE * SQUARE_COLUMNS as f32 / 2.0;
-                        match what {
-                            "tree" => {
-                                let s = Scenery::new_tree(&gl,vec3(x,y,z),&tree_model);
-                                scenery_instances.push(s)
-                            }
-                            "house" => {
-                                let s = Scenery::new_house(&gl,vec3(x,y,z),&house_model);
-                                scenery_instances.push(s)
-                            }
-                            "office1" => {
-                                let s = Scenery::new_office1(&gl,vec3(x,y,z),&office1_model);
-                                scenery_instances.push(s)
-                            }
-                            _ => println!("Ain't special"),
-                        }
-                    } else if (&record[0]).starts_with("d") {
-                        println!("Description {}", &record[1]);
-                        landscape_object.description = String::from(&record[1]);
-                    } else if !(&record[0]).starts_with("s") {
-                        rows.push(record.clone());
-                        if rows.len() == 4 {
-                            landscape_object.vertices.push(Landscape::push_record(&mut vertices, &rows[2], None));
-                            landscape_object.vertices.push(Landscape::push_record(&mut vertices, &record, None));
-                            landscape_object.vertices.push(Landscape::push_record(&mut vertices, &rows[0], None));
-                        } else {
-                            landscape_object.vertices.push(Landscape::push_record(&mut vertices, &record, None));
-                        }
+            println!("-----------------------    {}       {}/{}", filename, xyz.x, xyz.z);
+            let tile = parse_tile(&filename).unwrap_or_else(|e| panic!("{}", e));
+            for problem in check_tile(&tile).iter() {
+                println!("{}", problem);
+            }
+            for object in tile.objects.iter() {
+                let x = object.x * SQUARE_SIZE - SQUARE_SIZE * SQUARE_COLUMNS as f32 / 2.0;
+                let y = object.y * SQUARE_SIZE;
+                let z = object.z * SQUARE_SIZE - SQUARE_SIZE * SQUARE_COLUMNS as f32 / 2.0;
+                match object.what.as_str() {
+                    "tree" => {
+                        let s = Scenery::new_tree(vec3(x,y,z));
+                        scenery_instances.push(s)
+                    }
+                    "house" => {
+                        let s = Scenery::new_house(vec3(x,y,z));
+                        scenery_instances.push(s)
+                    }
+                    "office1" => {
+                        let s = Scenery::new_office1(vec3(x,y,z));
+                        scenery_instances.push(s)
+                    }
+                    "hospital" => {
+                        let s = Scenery::new_hospital(vec3(x,y,z));
+                        scenery_instances.push(s)
+                    }
+                    "stop" => {
+                        bus_stops.push(BusStop::new(object.name.clone(), vec3(x,y,z)))
+                    }
+                    _ => println!("Ain't special"),
+                }
+            }
+            for polygon in tile.polygons.iter() {
+                println!("Description {}", polygon.description);
+                let mut landscape_object: LandscapeObject = LandscapeObject {
+                    vertices: vec![],
+                    description: polygon.description.clone(),
+                };
+                // a fan of triangles, for a quad that is 0,1,2 then 2,3,0
+                let rows = &polygon.vertices;
+                for (i, vertex) in rows.iter().enumerate() {
+                    if i >= 3 {
+                        landscape_object.vertices.push(Landscape::push_record(&mut vertices, &rows[i - 1], None));
+                        landscape_object.vertices.push(Landscape::push_record(&mut vertices, vertex, None));
+                        landscape_object.vertices.push(Landscape::push_record(&mut vertices, &rows[0], None));
                     } else {
-                        if &record[1] == "1" { Landscape::create_side(&mut vertices, &mut rows, 0, 1); }
-                        if &record[2] == "1" { Landscape::create_side(&mut vertices, &mut rows, 1, 2); }
-                        if &record[3] == "1" { Landscape::create_side(&mut vertices, &mut rows, 2, 3); }
-                        if &record[4] == "1" { Landscape::create_side(&mut vertices, &mut rows, 3, 0); }
-                        landscape_objects.push(landscape_object.clone());
-                        landscape_object.vertices.clear();
-                        landscape_object.description.clear();
-                        rows.clear();
+                        landscape_object.vertices.push(Landscape::push_record(&mut vertices, vertex, None));
+                    }
+                }
+                for side in 0..4 {
+                    if polygon.sides[side] && side < rows.len() {
+                        Landscape::create_side(&mut vertices, rows, side, (side + 1) % rows.len());
                     }
                 }
+                landscape_objects.push(landscape_object);
             }
 
             //println!("VERTICES {}", vertices.len());
 
-            let (mut vbo, mut vao) = (0, 0);
-            if vertices.len() > 0 {
-                gl.GenVertexArrays(1, &mut vao);
-                gl.GenBuffers(1, &mut vbo);
-
-                gl.BindVertexArray(vao);
-
-                gl.BindBuffer(gl::ARRAY_BUFFER, vbo);
-                gl.BufferData(gl::ARRAY_BUFFER,
-                              (vertices.len() * mem::size_of::<gl::types::GLfloat>()) as gl::types::GLsizeiptr,
-                              &vertices[0] as *const f32 as *const c_void,
-                              gl::STATIC_DRAW);
-
-                let stride = 5 * mem::size_of::<gl::types::GLfloat>() as gl::types::GLsizei;
-                gl.VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
-                gl.EnableVertexAttribArray(0);
-                gl.VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, stride, (3 * mem::size_of::<gl::types::GLfloat>()) as *const c_void);
-                gl.EnableVertexAttribArray(1);
-            }
-
-            let texture = if TEXTURE_LOADED == -1 {
-                if image_file.ends_with(".png") {
-                    TEXTURE_LOADED = create_texture_png(&gl, image_file) as i32;
-                } else {
-                    TEXTURE_LOADED = create_texture_jpg(&gl, image_file) as i32;
-                };
-                TEXTURE_LOADED as u32
-            } else {
-                TEXTURE_LOADED as u32
-            };
-
-            (vbo, vao, texture, (vertices.len() as f32 * 0.2) as usize)
+            let vertices_count = (vertices.len() as f32 * 0.2) as usize;
+            (vertices, vertices_count)
         };
 
 
         Landscape {
             //id:id,
-            texture,
-            vao,
-            height_map: height_map.clone(),
+            height_map: height_map.to_vec(),
             xyz,
+            vertices,
             vertices_count,
             filename,
             landscape_objects,
             scenery_instances,
+            bus_stops,
         }
     }
 
@@ -240,18 +203,8 @@ impl Landscape {
         }
         return found;
     }
-    pub fn scenery_at(&self, x: f32, z: f32) -> Option<&Scenery> {
-        let mut found: Option<&Scenery> = None;
-        let xyz = vec3(x,0.0,z);
-        for scenery in self.scenery_instances.iter() {
-            if (self.xyz + scenery.position).distance(xyz) < scenery.collision_radius {
-                found = Some(scenery)
-            }
-        }
-        return found;
-    }
 
-    fn create_side(mut vertices: &mut Vec<f32>, rows: &mut Vec<StringRecord>, side: usize, side2: usize) {
+    fn create_side(mut vertices: &mut Vec<f32>, rows: &[TileVertex], side: usize, side2: usize) {
         Landscape::push_record(&mut vertices, &rows[side], None);
         Landscape::push_record(&mut vertices, &rows[side2], None);
         Landscape::push_record(&mut vertices, &rows[side2], Some(0.0));
@@ -261,22 +214,21 @@ impl Landscape {
         Landscape::push_record(&mut vertices, &rows[side], None);
     }
 
-    fn push_record(vertices: &mut Vec<f32>, record: &StringRecord, height: Option<f32>) -> Vector3<f32> {
-        //println!("RECORD {} {} {}", &record[0], &record[1], &record[2], );
+    fn push_record(vertices: &mut Vec<f32>, record: &TileVertex, height: Option<f32>) -> Vector3<f32> {
         let offset = 3.2; //SQUARE_SIZE * SQUARE_COLUMNS as f32 /2.0;
 
-        let x = (&record[0]).parse::<f32>().unwrap() * SQUARE_SIZE - offset;
+        let x = record.x * SQUARE_SIZE - offset;
         vertices.push(x);
         let y = if height.is_none() {
-            (&record[1]).parse::<f32>().unwrap() * SQUARE_SIZE
+            record.y * SQUARE_SIZE
         } else {
             height.unwrap() * SQUARE_SIZE
         };
         vertices.push(y);
-        let z = (&record[2]).parse::<f32>().unwrap() * SQUARE_SIZE - offset;
+        let z = record.z * SQUARE_SIZE - offset;
         vertices.push(z);
-        vertices.push((&record[3]).parse::<f32>().unwrap() / IMAGE_SCALE_FACTOR);
-        vertices.push((IMAGE_SCALE_FACTOR - (&record[4]).parse::<f32>().unwrap()) / IMAGE_SCALE_FACTOR);
+        vertices.push(record.u / IMAGE_SCALE_FACTOR);
+        vertices.push((IMAGE_SCALE_FACTOR - record.v) / IMAGE_SCALE_FACTOR);
         return Vector3::new(x, y, z);
     }
 
@@ -295,23 +247,4 @@ impl Landscape {
 
         return height;
     }
-
-    pub fn render(&mut self, gl: &gl::Gl, view: &Matrix4<f32>, projection: &Matrix4<f32>, here: Matrix4<f32>, _wrapped_position: Vector3<f32>, our_shader: u32) {
-
-        for s in self.scenery_instances.iter_mut() {
-            s.render(gl,view,projection,our_shader,_wrapped_position)
-        }
-        unsafe {
-            //gl.UseProgram(our_shader);
-            gl.ActiveTexture(gl::TEXTURE0);
-            gl.BindTexture(gl::TEXTURE_2D, self.texture);
-            gl.BindVertexArray(self.vao);
-
-            gl_matrix4(gl, our_shader, here, "model");
-            gl_matrix4(gl, our_shader, *view, "view");
-            gl_matrix4(gl, our_shader, *projection, "projection");
-            gl.DrawArrays(gl::TRIANGLES, 0, self.vertices_count as i32);
-        }
-    }
 }
-
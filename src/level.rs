@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::map_manifest::MapManifest;
+
+pub const DEFAULT_LEVEL: &str = "resources/levels/level1.toml";
+
+/// Everything that makes one level different from another, read from a toml file.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Level {
+    pub name: String,
+    pub map: MapManifest,
+    /// x,y,z in world units, 0,0 is the middle of the city
+    pub bus_start: [f32; 3],
+    /// degrees about y, 0 faces north
+    pub bus_heading: f32,
+    pub max_passengers: usize,
+    /// new passengers only arrive when there are this many humans or fewer
+    pub min_humans: usize,
+    /// chance 0.0 to 1.0 of each bus stop getting another passenger when more are needed,
+    /// a map without stops uses it for each tile instead
+    pub spawn_chance: f32,
+    /// a passenger turns into a zombie somewhere between these two times
+    pub zombie_seconds: [f32; 2],
+    /// how likely each kind of zombie in zombies.toml is, any left out never turn up
+    pub zombie_weights: BTreeMap<String, f32>,
+    pub lives: i32,
+    /// which bus from buses.toml the level is driven in
+    pub bus: String,
+    /// how many riders fit on the bus at once
+    pub bus_capacity: usize,
+    /// cars driving round the roads on their own
+    pub traffic: usize,
+    pub target_score: i32,
+    /// level file to load when target_score is reached, none means the game is won
+    pub next: Option<String>,
+    /// endless waves of zombies instead of playing to target_score
+    pub horde: Option<HordeSettings>,
+}
+
+/// How the waves of a horde level grow, a [horde] table in the level file.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct HordeSettings {
+    /// zombies in the first wave
+    pub first_wave: usize,
+    /// extra zombies in each wave after that
+    pub wave_increase: usize,
+    /// each wave is this much faster again, 0.1 makes the second wave 1.1 times the speed
+    pub speed_increase: f32,
+    /// rest between a wave being cleared and the next one, and before the first
+    pub breather_seconds: f32,
+}
+
+impl Level {
+    pub fn load(filename: &str) -> Result<Level, String> {
+        let contents = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        let level: Level = toml::from_str(&contents).map_err(|e| format!("{}: {}", filename, e))?;
+        if level.map.size == 0 {
+            return Err(format!("{}: map size must be at least 1", filename));
+        }
+        // the time is picked between the two, they can't be the same
+        if level.zombie_seconds[0] >= level.zombie_seconds[1] {
+            return Err(format!("{}: zombie_seconds should be [shortest, longest] with longest the bigger", filename));
+        }
+        if level.spawn_chance < 0.0 || level.spawn_chance > 1.0 {
+            return Err(format!("{}: spawn_chance should be between 0.0 and 1.0", filename));
+        }
+        if level.zombie_weights.values().any(|w| *w < 0.0) || level.zombie_weights.values().sum::<f32>() <= 0.0 {
+            return Err(format!("{}: zombie_weights can't be negative and need at least one above 0.0", filename));
+        }
+        if level.lives <= 0 {
+            return Err(format!("{}: lives should be at least 1", filename));
+        }
+        if let Some(horde) = &level.horde {
+            if horde.first_wave == 0 || horde.speed_increase < 0.0 || horde.breather_seconds < 0.0 {
+                return Err(format!("{}: horde needs a first_wave of at least 1 and no negative speed_increase or breather_seconds", filename));
+            }
+        }
+        if level.bus_capacity == 0 {
+            return Err(format!("{}: bus_capacity should be at least 1", filename));
+        }
+        Ok(level)
+    }
+}
@@ -5,26 +5,47 @@ use std::time::Instant;
 
 use cgmath::{Matrix4, Point3, Vector3, Vector4};
 
+use crate::command_line::CommandLine;
 use crate::game::Runtime;
 
 //use std::time::Instant;
 
+mod bus_stop;
+mod bus_tuning;
+mod command_line;
+mod controls;
 mod game;
 mod gl;
 mod cube;
 mod gl_helper;
 mod flying_camera;
 mod handle_javascript;
+mod high_scores;
 mod landscape;
+mod level;
+mod map_manifest;
+mod mission;
 //mod shadow_shaders;
 //mod openglshadow;
 mod ground;
+mod spatial_grid;
+mod spawn_policy;
 mod special_effects;
 mod sound;
 mod car_main_player;
 mod map_display;
 mod scenery;
+mod tile_file;
+mod traffic;
 mod passengers;
+mod path_finding;
+mod recording;
+mod rider;
+mod save_game;
+mod road_graph;
+mod world;
+mod world_renderer;
+mod zombie_type;
 
 pub const WIDTH: u32 = 800;
 pub const HEIGHT: u32 = 600;
@@ -73,7 +94,8 @@ pub fn get_vector4_as_string(v: Vector4<f32>) -> String {
 
 
 fn main() {
-    let runtime = Runtime::new();
+    let command_line = CommandLine::parse();
+    let runtime = Runtime::new(command_line);
 
     emscripten_main_loop::run(runtime);
 }
@@ -4,14 +4,14 @@ use std::{mem};
 use std::os::raw::c_void;
 use std::ptr;
 
-use csv::{StringRecord, Trim};
-
 use crate::{gl, get_start_time, output_elapsed};
 use crate::gl_helper::{gl_matrix4, gl_vec3, gl_vec2};
 use crate::gl_helper::shader::create_shader;
 use crate::gl_helper::texture::{create_texture_jpg, create_texture_png};
-use crate::ground::BY;
+use crate::ground::Ground;
 use crate::landscape::{IMAGE_SCALE_FACTOR, SQUARE_COLUMNS, SQUARE_ROWS, };
+use crate::map_manifest::MapManifest;
+use crate::tile_file::{parse_tile, TileVertex};
 
 use self::cgmath::{Matrix4, ortho, vec3, Vector3, vec2};
 
@@ -74,6 +74,10 @@ pub struct MapDisplay {
     pub vertices_count: usize,
     pub map_vao: u32,
     pub map_vertices_count: usize,
+    /// how wide the whole city is drawn on the map
+    pub map_width: f32,
+    pub stops_vao: u32,
+    pub stops_vertices_count: usize,
 }
 
 const SCALE: f32 = 0.125;
@@ -99,8 +103,9 @@ impl MapDisplay {
     const GREY_TOP:f32 = 0.01;
     const GREY_RIGHT:f32 = 0.945;
 
-    pub fn new(gl: &gl::Gl) -> MapDisplay {
+    pub fn new(gl: &gl::Gl, map_manifest: &MapManifest) -> MapDisplay {
         let start = get_start_time();
+        let mut stop_vertices: Vec<f32> = vec![];
         let (our_shader, texture, vao, vertices_count) = unsafe {
 
 
@@ -116,7 +121,7 @@ impl MapDisplay {
 
             ];
 
-            MapDisplay::create_vertices_of_ground_map(&mut vertices);
+            MapDisplay::create_vertices_of_ground_map(&mut vertices, &mut stop_vertices, map_manifest);
 
             println!("MAP VERTICES {}", vertices.len());
 
@@ -140,6 +145,7 @@ impl MapDisplay {
         ];
 
         let map_vao = unsafe { MapDisplay::bind_vertices(gl, &mut map_triangle) };
+        let stops_vao = unsafe { MapDisplay::bind_vertices(gl, &mut stop_vertices) };
 
         output_elapsed(start,"MAP new() completed in ");
 
@@ -150,6 +156,9 @@ impl MapDisplay {
             vertices_count,
             map_vao,
             map_vertices_count: map_triangle.len(),
+            map_width: map_manifest.size as f32 * SCALE,
+            stops_vao,
+            stops_vertices_count: stop_vertices.len(),
         }
     }
 
@@ -176,43 +185,34 @@ impl MapDisplay {
         vao
     }
 
-    fn create_vertices_of_ground_map(mut vertices: &mut Vec<f32>) {
-        for xx in 0..BY {
-            for zz in 0..BY {
-                let filename = format!("resources/road_{}_{}.txt", xx, zz);
+    fn create_vertices_of_ground_map(mut vertices: &mut Vec<f32>, mut stop_vertices: &mut Vec<f32>, map_manifest: &MapManifest) {
+        for xx in 0..map_manifest.size {
+            for zz in 0..map_manifest.size {
+                let filename = map_manifest.tile_filename(xx, zz);
                 println!("MAP FILE -----------------------    {} ", filename);
-                let mut rows: Vec<StringRecord> = vec![];
-                let reader = csv::ReaderBuilder::new().has_headers(false).flexible(true)
-                    .comment(Some(b'#')).trim(Trim::All).from_path(&filename).expect(&filename);
-                for record in reader.into_records() {
-                    if record.is_ok() {
-                        let record = record.unwrap();
-                        if (&record[0]).starts_with("o") {
-
-                            let what = &record[1];
-                            let x = &record[2].parse::<f32>().unwrap() * SCALE;
-                            let z = &record[4].parse::<f32>().unwrap() * SCALE;
-                            match what {
-                                "house" => {
-                                    MapDisplay::draw_house_on_map(&mut vertices, xx, zz, x, z);
-                                }
-                                _ => println!("Ain't special"),
-                            }
-
-
-                        } else if (&record[0]).starts_with("d") {
-                            println!("Description {}", &record[1]);
-                        } else if !(&record[0]).starts_with("s") {
-                            rows.push(record.clone());
-                            if rows.len() == 4 {
-                                MapDisplay::push_record(&mut vertices, &rows[2], xx as f32, zz as f32);
-                                MapDisplay::push_record(&mut vertices, &record, xx as f32, zz as f32);
-                                MapDisplay::push_record(&mut vertices, &rows[0], xx as f32, zz as f32);
-                            } else {
-                                MapDisplay::push_record(&mut vertices, &record, xx as f32, zz as f32);
-                            }
+                let tile = parse_tile(&filename).unwrap_or_else(|e| panic!("{}", e));
+                for object in tile.objects.iter() {
+                    let x = object.x * SCALE;
+                    let z = object.z * SCALE;
+                    if object.what == "house" {
+                        MapDisplay::draw_house_on_map(&mut vertices, xx, zz, x, z);
+                    }
+                    if object.what == "stop" {
+                        // drawn in their own colour so centre the square on the stop
+                        let x = object.x / SQUARE_COLUMNS as f32 * SCALE - 0.015;
+                        let z = object.z / SQUARE_ROWS as f32 * SCALE - 0.015;
+                        MapDisplay::draw_house_on_map(&mut stop_vertices, xx, zz, x, z);
+                    }
+                }
+                for polygon in tile.polygons.iter() {
+                    let rows = &polygon.vertices;
+                    for (i, vertex) in rows.iter().enumerate() {
+                        if i >= 3 {
+                            MapDisplay::push_record(vertices, &rows[i - 1], xx as f32, zz as f32);
+                            MapDisplay::push_record(vertices, vertex, xx as f32, zz as f32);
+                            MapDisplay::push_record(vertices, &rows[0], xx as f32, zz as f32);
                         } else {
-                            rows.clear();
+                            MapDisplay::push_record(vertices, vertex, xx as f32, zz as f32);
                         }
                     }
                 }
@@ -247,7 +247,7 @@ impl MapDisplay {
         vertices.push(0.0);
         vertices.push(MapDisplay::WHITE_RIGHT); vertices.push(MapDisplay::WHITE_TOP);
     }
-    fn push_record(vertices: &mut Vec<f32>, record: &StringRecord, x: f32, y: f32) {
+    fn push_record(vertices: &mut Vec<f32>, record: &TileVertex, x: f32, y: f32) {
         //println!("RECORD {} {} {}", &record[0], &record[1], &record[2], );
         //let offset = 3.2; //SQUARE_SIZE * SQUARE_COLUMNS as f32 /2.0;
 
@@ -255,40 +255,33 @@ impl MapDisplay {
 
         //println!("MAP X={} ,Y={} ,Z={} ", x, y, z);
 
-        vertices.push((&record[3]).parse::<f32>().unwrap() / IMAGE_SCALE_FACTOR);
-        vertices.push((IMAGE_SCALE_FACTOR - (&record[4]).parse::<f32>().unwrap()) / IMAGE_SCALE_FACTOR);
+        vertices.push(record.u / IMAGE_SCALE_FACTOR);
+        vertices.push((IMAGE_SCALE_FACTOR - record.v) / IMAGE_SCALE_FACTOR);
     }
 
-    fn push_xyz(vertices: &mut Vec<f32>, record: &&StringRecord, x: f32, y: f32) {
+    fn push_xyz(vertices: &mut Vec<f32>, record: &&TileVertex, x: f32, y: f32) {
         let xx = x * SCALE;
         let yy = y * SCALE;
 
-        let x = (&record[0]).parse::<f32>().unwrap() / SQUARE_COLUMNS as f32;
+        let x = record.x / SQUARE_COLUMNS as f32;
         vertices.push(x * SCALE + xx);
-        let y = (&record[2]).parse::<f32>().unwrap() / SQUARE_ROWS as f32;
+        let y = record.z / SQUARE_ROWS as f32;
         vertices.push(y * SCALE + yy);
         let z = 0.0;
         vertices.push(z);
     }
     pub fn render(&mut self, gl: &gl::Gl, player_position: Vector3<f32>) {
         let position_map = vec3(0.4, 0.4, 0.0);
-        /*  BY=5
-         */
-        let scale_xz = 0.0193;
-        let centre_map_offset = vec3(-0.31, 0.31, 0.0);
-        let map_offset = vec3(0.4, 0.4, 0.0);
-        /*
-        // BY 3
-        let scale_xz = 0.020;
-        let centre_map_offset = vec3(-0.193, 0.193, 0.0);
-        let map_offset = vec3(0.62, 0.62, 0.0);
-         */
+        // each tile is SCALE wide on the map, so the city is map_width wide and repeats every map_width
+        let scale_xz = SCALE / Ground::MUL;
+        let centre_map_offset = vec3(-self.map_width / 2.0, self.map_width / 2.0, 0.0);
 
 
         let colour = vec3(0.0, 0.0, 0.0);
         let player_model: Matrix4<f32> = Matrix4::from_translation(vec3(0.0, 0.0, 0.10) + position_map);
 
         let red = vec3(1.0, 0.0, 0.0);
+        let stop_colour = vec3(1.0, 1.0, 0.0);
 
         let projection: Matrix4<f32> =
             ortho(-2.0, 2.0, -2.0, 2.0, -1.0, 100.0);
@@ -307,14 +300,22 @@ impl MapDisplay {
 
             for x in -1..2 {
                 for y in -1..2 {
-                    let this_offset = centre_map_offset + position_map - vec3(map_offset.x * x as f32, map_offset.y * y as f32, 0.0);
+                    let this_offset = centre_map_offset + position_map;
 
-                    let scaled_player_position = vec3(player_position.x * -scale_xz + x as f32, player_position.z * scale_xz + y as f32, 0.0) +
+                    let scaled_player_position = vec3(player_position.x * -scale_xz + x as f32 * self.map_width, player_position.z * scale_xz + y as f32 * self.map_width, 0.0) +
                         this_offset;
                     let model: Matrix4<f32> = Matrix4::from_translation(scaled_player_position);
                     gl_matrix4(gl, self.shader, model, "model");
                     gl_vec2(gl, self.shader, vec2(0.670,0.570), "screen");
                     gl.DrawArrays(gl::TRIANGLES, 0, self.vertices_count as i32 /5);
+
+                    if self.stops_vertices_count > 0 {
+                        gl_vec3(gl, self.shader, stop_colour, "colour");
+                        gl.BindVertexArray(self.stops_vao);
+                        gl.DrawArrays(gl::TRIANGLES, 0, self.stops_vertices_count as i32 /5);
+                        gl_vec3(gl, self.shader, colour, "colour");
+                        gl.BindVertexArray(self.vao);
+                    }
                 }
             }
 
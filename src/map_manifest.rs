@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// The city is size x size tiles, tile x,z is read from <tiles>_<x>_<z>.txt
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct MapManifest {
+    pub size: usize,
+    pub tiles: String,
+}
+
+impl MapManifest {
+    pub fn tile_filename(&self, x: usize, z: usize) -> String {
+        format!("{}_{}_{}.txt", self.tiles, x, z)
+    }
+}
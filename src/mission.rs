@@ -0,0 +1,161 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+pub const MISSIONS_FILE: &str = "resources/missions.toml";
+
+/// What has to be done to complete a mission, the type field in the toml picks which.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type")]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Objective {
+    /// drop off count riders at their stops, or pick up count on a map without stops
+    #[serde(rename = "deliver")]
+    DELIVER { count: i32 },
+    /// drive to tile x, z of the map, the one loaded from <tiles>_<x>_<z>.txt
+    #[serde(rename = "reach")]
+    REACH { tile: [usize; 2] },
+    /// have count zombies blow up on the bus and still have a life left
+    #[serde(rename = "survive")]
+    SURVIVE { count: i32 },
+}
+
+/// One [[mission]] table in missions.toml.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Mission {
+    pub name: String,
+    /// shown at the start and on the results screen
+    pub description: String,
+    /// level file the mission is played on, its target_score and next are ignored
+    pub level: String,
+    pub objective: Objective,
+    /// time limit, none for as long as it takes
+    pub seconds: Option<f32>,
+    /// any time with a wheel off the road fails the mission
+    #[serde(default)]
+    pub no_off_road: bool,
+    /// lives to start with instead of the level's
+    pub lives: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct MissionsFile {
+    mission: Vec<Mission>,
+}
+
+pub fn load_missions(filename: &str) -> Result<Vec<Mission>, String> {
+    let contents = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let file: MissionsFile = toml::from_str(&contents).map_err(|e| format!("{}: {}", filename, e))?;
+    for mission in file.mission.iter() {
+        if file.mission.iter().filter(|m| m.name == mission.name).count() > 1 {
+            return Err(format!("{}: {} is there more than once", filename, mission.name));
+        }
+        match mission.objective {
+            Objective::DELIVER { count } | Objective::SURVIVE { count } if count <= 0 => {
+                return Err(format!("{}: {} needs a count of at least 1", filename, mission.name));
+            }
+            _ => {}
+        }
+        if mission.seconds.is_some_and(|s| s <= 0.0) || mission.lives.is_some_and(|l| l <= 0) {
+            return Err(format!("{}: {} seconds and lives should be above 0", filename, mission.name));
+        }
+    }
+    Ok(file.mission)
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum MissionState {
+    RUNNING,
+    SUCCEEDED,
+    /// why it failed
+    FAILED(String),
+}
+
+/// What happened during one world step that a mission might care about.
+#[derive(Default)]
+pub struct MissionStep {
+    pub delivered: i32,
+    pub zombie_explosions: i32,
+    pub off_road: bool,
+    /// x, z of the tile the bus is on
+    pub tile: (usize, usize),
+    pub lives: i32,
+}
+
+/// A mission being played, how far it has got and whether it is over.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MissionProgress {
+    pub mission: Mission,
+    pub state: MissionState,
+    pub seconds: f32,
+    /// deliveries or explosions so far
+    pub count: i32,
+}
+
+impl MissionProgress {
+    pub fn new(mission: Mission) -> MissionProgress {
+        MissionProgress { mission, state: MissionState::RUNNING, seconds: 0.0, count: 0 }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.state != MissionState::RUNNING
+    }
+
+    /// Moves the mission on by one step, true if that finished it.
+    pub fn update(&mut self, delta: f32, step: &MissionStep) -> bool {
+        if self.finished() {
+            return false;
+        }
+        self.seconds += delta;
+        match self.mission.objective {
+            Objective::DELIVER { count } => {
+                self.count += step.delivered;
+                if self.count >= count {
+                    self.state = MissionState::SUCCEEDED;
+                }
+            }
+            Objective::REACH { tile } => {
+                if step.tile == (tile[0], tile[1]) {
+                    self.state = MissionState::SUCCEEDED;
+                }
+            }
+            Objective::SURVIVE { count } => {
+                self.count += step.zombie_explosions;
+                if self.count >= count && step.lives > 0 {
+                    self.state = MissionState::SUCCEEDED;
+                }
+            }
+        }
+        if self.finished() {
+            return true;
+        }
+        if step.lives <= 0 {
+            self.state = MissionState::FAILED(String::from("out of lives"));
+        } else if self.mission.no_off_road && step.off_road {
+            self.state = MissionState::FAILED(String::from("went off the road"));
+        } else if self.mission.seconds.is_some_and(|limit| self.seconds >= limit) {
+            self.state = MissionState::FAILED(String::from("out of time"));
+        }
+        self.finished()
+    }
+
+    /// One line for the HUD and the results screen.
+    pub fn progress(&self) -> String {
+        let done = match self.mission.objective {
+            Objective::DELIVER { count } => format!("delivered {}/{}", self.count, count),
+            Objective::REACH { tile } => format!("get to tile {}_{}", tile[0], tile[1]),
+            Objective::SURVIVE { count } => format!("survived {}/{} explosions", self.count, count),
+        };
+        match self.mission.seconds {
+            Some(limit) => format!("{} {} left", done, clock(limit - self.seconds)),
+            None => format!("{} {}", done, clock(self.seconds)),
+        }
+    }
+}
+
+/// m:ss
+pub fn clock(seconds: f32) -> String {
+    let seconds = seconds.max(0.0) as i32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
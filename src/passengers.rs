@@ -1,25 +1,39 @@
 
-use cgmath::{Angle, Deg, EuclideanSpace, InnerSpace, Matrix4, MetricSpace, Point3, Rad, Transform, vec3, Vector3, Zero};
+use cgmath::{Angle, Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, Rad, Transform, vec3, Vector3, Zero};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use crate::{get_start_time, gl, output_elapsed, point2vec};
-use crate::flying_camera::Flying_Camera;
-use crate::game::MovementAndCollision;
-use crate::gl_helper::instance_model::ModelInstance;
-use crate::ground::{BY, Ground};
-use crate::landscape::{ SQUARE_COLUMNS, SQUARE_SIZE};
+use crate::{get_start_time, output_elapsed, point2vec};
+use crate::bus_stop::PICKUP_SPEED;
+use crate::ground::Ground;
+use crate::path_finding::find_path;
 use crate::special_effects::SpecialEffects;
+use crate::world::{GameRng, MovementAndCollision};
+use crate::zombie_type::ZombieType;
 //use crate::TICKDEBUG;
 //use crate::TICKPRINTCOUNTER;
 //use crate::tickprintln;
 
+/// What happened to a passenger in one update, for the world to score.
+#[derive(Default)]
+pub struct PassengerOutcome {
+    pub finished: bool,
+    pub picked_up: bool,
+    /// reached the bus while it was going slowly and blew up on it
+    pub exploded_on_bus: bool,
+    /// the bus hit it going fast, run_over as well if that was its last hit
+    pub hit_by_bus: bool,
+    pub run_over: bool,
+    pub spat: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Passenger {
-    pub(crate) model_instance: Vec<ModelInstance>,
-    animate: f32,
+    pub(crate) animate: f32,
     pub zombie: bool,
-    zombie_countdown: f32,
-    turning_zombie: i32,
-    zombie_exploding: bool,
+    pub(crate) zombie_countdown: f32,
+    turning_zombie: f32,
+    pub(crate) zombie_exploding: bool,
     pub(crate) movement_collision: MovementAndCollision,
     matrix: Matrix4<f32>,
     rotation_y_axis: Matrix4<f32>,
@@ -29,46 +43,81 @@ pub struct Passenger {
     rotation_x_axis: Matrix4<f32>,
     pub rotation_x: f32,
     ahead_force: Matrix4<f32>,
-    moves_since_last_change: i32,
+    moves_since_last_change: f32,
     nsew: Vec<bool>,
     old_nsew: Vec<bool>,
-    nsew_change_clicks: i128,
+    nsew_change_clicks: f32,
     target_angle: f32,
     previous_target_angle: f32,
-    applied_rotation: Matrix4<f32>,
+    pub(crate) applied_rotation: Matrix4<f32>,
     gravity: f32,
+    gravity_timer: f32,
     forward_reverse: f32,
     dir: Vector3<f32>,
     speed: f32,
     animate_speed: f32,
+    /// road graph nodes a zombie is walking through to get to the bus
+    path: Vec<usize>,
+    replan_timer: f32,
+    /// the bus stop being queued at, humans stand still until the bus comes
+    pub waiting_at: Option<usize>,
+    pub queue_place: usize,
+    /// the bus stop they want to go to once on the bus
+    pub destination: Option<usize>,
+    /// what they turn into, picked when they arrive so it follows the seed
+    pub zombie_type_index: usize,
+    pub zombie_type: ZombieType,
+    hits_left: i32,
+    /// counts down from HIT_AGAIN_SECONDS after a hit
+    hit_timer: f32,
+    spit_timer: f32,
 }
 
 const MODEL_HEIGHT: f32 = 0.10;
 const GRAVITY_ADD: f32 = 0.05;
 const GRAVITY_MAX: f32 = 0.05;
+const GRAVITY_SECONDS: f32 = 1.5;
 pub const PASSENGER_SCALE: f32 = 0.004;
-const ZOMBIE_SCALE: f32 = 0.006;
-const ZOMBIE_SPEED: f32 = 0.25;
+pub const ANIMATION_FRAMES: usize = 4;
 const GRAVITY: bool = true;
-const ZOMBIE_DISTANCE_GOT_TO_BUS: f32 = 0.2;
+// bus speed in world units per second that hits a zombie instead of letting it blow up on the bus
+pub const RUN_OVER_SPEED: f32 = 1.5;
+// how far a zombie that can take another hit gets pushed away from the bus
+const KNOCK_BACK: f32 = 0.5;
+// a zombie that has just been hit can't be hit again for this long, driving over one is one hit
+const HIT_AGAIN_SECONDS: f32 = 0.5;
+// how fast a human steps out of the way of a car, world units per second
+const DODGE_SPEED: f32 = 1.0;
+// blocks thrown up by a car hitting a human
+const HIT_BY_CAR_BLOCKS: usize = 6;
+const TURNING_ZOMBIE_SECONDS: f32 = 2.5;
+const TURNING_ZOMBIE_EFFECT_SECONDS: f32 = 0.125;
+// how long to keep going after a turn before thinking about another one
+const SETTLE_SECONDS: f32 = 1.5;
+const NSEW_DECIDE_SECONDS: f32 = 0.25;
+// zombies work out a new path this often as the bus will have moved
+const REPLAN_SECONDS: f32 = 1.0;
+const WAYPOINT_REACHED: f32 = 0.3;
+// don't stop to turn for less than this, it would stop and start all the way along a road
+const WAYPOINT_TURN_DEGREES: f32 = 10.0;
 
 
 const HUMAN_SEE_BUS: f32 = 4.0;
 const ZOMBIE_SEE_BUS: f32 = 8.0;
+// further from the bus than this nobody sees it close up, reaches it or gets picked up,
+// covers the square root of ZOMBIE_SEE_BUS and HUMAN_SEE_BUS
+pub const NEAR_BUS: f32 = 3.0;
 
 impl Passenger {
-    pub fn new(_gl: &gl::Gl, start_position: Vector3<f32>, instances: &Vec<ModelInstance>) -> Passenger {
+    pub fn new(start_position: Vector3<f32>, zombie_type_index: usize, zombie_type: &ZombieType, rng: &mut GameRng) -> Passenger {
         let start = get_start_time();
 
-        let mut rng = rand::thread_rng();
-
         let speed = rng.gen_range(0.10, 0.15);
         let p = Passenger {
-            model_instance: instances.clone(),
             animate: 0.0,
             zombie: false,
             zombie_countdown: 6.0,
-            turning_zombie: 0,
+            turning_zombie: 0.0,
             zombie_exploding: false,
             movement_collision: MovementAndCollision::new(MODEL_HEIGHT * 1.25, start_position),
             matrix: Matrix4::from_translation(start_position),
@@ -78,79 +127,131 @@ impl Passenger {
             angle_of_rotation: 0.0,
             rotation_x: 0.0,
             ahead_force: Matrix4::from_translation(vec3(0.0, 0.0, 0.1)),
-            moves_since_last_change: 0,
+            moves_since_last_change: 0.0,
             target_angle: 180.0,
             previous_target_angle: 0.0,
             nsew: vec![false, false, false, false],
             old_nsew: vec![false, false, false, false],
-            nsew_change_clicks: 100,
+            nsew_change_clicks: 2.5,
             rotation_angle: 180.0,
             applied_rotation: Matrix4::from_translation(vec3(0.0, 0.0, 0.0)),
             forward_reverse: 1.0,
             dir: vec3(0.0, 0.00, 0.0),
             gravity: GRAVITY_ADD,
+            gravity_timer: 0.0,
             speed,
             animate_speed: speed * 84.0,
+            path: vec![],
+            replan_timer: 0.0,
+            waiting_at: None,
+            queue_place: 0,
+            destination: None,
+            zombie_type_index,
+            zombie_type: zombie_type.clone(),
+            hits_left: zombie_type.hits,
+            hit_timer: 0.0,
+            spit_timer: 0.0,
         };
         output_elapsed(start, "time elapsed for passenger new()");
         p
     }
 
-    pub fn set_random_time_to_zombie(&mut self) {
-        let mut rng = rand::thread_rng();
-        self.zombie_countdown = rng.gen_range(40.0,120.0);
+    /// Thrown off the bus having already turned.
+    pub fn new_zombie(start_position: Vector3<f32>, zombie_type_index: usize, zombie_type: &ZombieType, rng: &mut GameRng) -> Passenger {
+        let mut passenger = Passenger::new(start_position, zombie_type_index, zombie_type, rng);
+        passenger.zombie = true;
+        passenger.zombie_countdown = -1.0;
+        passenger.speed = zombie_type.speed;
+        passenger
+    }
+
+    /// zombie_seconds is the shortest and longest time before turning
+    pub fn set_random_time_to_zombie(&mut self, rng: &mut GameRng, zombie_seconds: [f32; 2]) {
+        self.zombie_countdown = rng.gen_range(zombie_seconds[0], zombie_seconds[1]);
         //self.zombie_countdown = rng.gen_range(4.0,12.0);
     }
 
 
-    pub fn update(&mut self, delta: f32, ground: &Ground, _camera: &Flying_Camera, tick: i128, special_effects: &mut SpecialEffects, chase_target: Vector3<f32>) -> (bool, bool, bool) {
-        let mut finished = false;
-        let mut add_score = false;
-        let mut zombie_explode = false;
+    /// bus_speed decides whether someone reaching the bus is picked up, and whether a zombie
+    /// gets run over or blows up on it. near_bus is false for anyone the world's spatial grid
+    /// says is further than NEAR_BUS, spit_range or reach away, they are treated as far off.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(&mut self, delta: f32, ground: &Ground, special_effects: &mut SpecialEffects, chase_target: Vector3<f32>, bus_speed: f32, near_bus: bool, rng: &mut GameRng) -> PassengerOutcome {
+        let mut outcome = PassengerOutcome::default();
         let original_matrix = self.matrix;
 
         let old_pos = self.movement_collision.position.clone();
         self.zombie_countdown = self.zombie_countdown - delta;
+        self.hit_timer -= delta;
 
         self.animate = self.animate + delta * self.animate_speed;
-        if self.animate as usize >= self.model_instance.len() || self.zombie_exploding {
+        if self.animate as usize >= ANIMATION_FRAMES || self.zombie_exploding {
             self.animate = 0.0;
         }
 
         if !self.zombie_exploding {
-            self.do_movement_updates(tick, special_effects,);
+            self.do_movement_updates(delta, special_effects, rng);
         } else {
             self.matrix.w.y = self.matrix.w.y - delta * 1.25;
             self.update_position();
         }
 
-        let half_width = (SQUARE_COLUMNS / 2) as f32 * SQUARE_SIZE * BY as f32;
-
         //println!("{} {} {} {}",self.thrust.w.x,self.thrust.w.y,self.thrust.w.z,self.thrust.w.w);
 
-        self.wrap_position_if_needed(half_width);
+        self.wrap_position_if_needed(ground.width());
 
-        let distance = chase_target.distance2(self.movement_collision.position);
-        if distance < 0.06 && !self.zombie {
-            finished = true;
-            add_score = true;
+        let distance = if near_bus {
+            ground.roads.wrapped_delta(self.movement_collision.position, chase_target).magnitude2()
+        } else {
+            f32::MAX
+        };
+        // anyone queuing at a stop gets on there as a rider instead
+        if distance < 0.06 && !self.zombie && bus_speed < PICKUP_SPEED && self.waiting_at.is_none() {
+            outcome.finished = true;
+            outcome.picked_up = true;
         }
-        if !self.zombie_exploding && distance < ZOMBIE_DISTANCE_GOT_TO_BUS && self.zombie {
-            special_effects.explosion(self.movement_collision.position);
-            //finished = true;
-            zombie_explode = true;
-            self.set_to_explode();
+        if !self.zombie_exploding && distance < self.zombie_type.reach && self.zombie && self.hit_timer <= 0.0 {
+            let fast = bus_speed >= RUN_OVER_SPEED;
+            outcome.hit_by_bus = fast;
+            self.hits_left -= 1;
+            self.hit_timer = HIT_AGAIN_SECONDS;
+            if self.hits_left <= 0 {
+                special_effects.explosion_sized(self.movement_collision.position, self.zombie_type.explosion, rng);
+                //finished = true;
+                if fast {
+                    outcome.run_over = true;
+                } else {
+                    outcome.exploded_on_bus = true;
+                }
+                self.set_to_explode();
+            } else {
+                special_effects.zombie(self.movement_collision.position, rng);
+                self.knock_back(ground, chase_target);
+            }
+        }
+        if !self.zombie_exploding && self.zombie && self.zombie_type.spit_range > 0.0 && distance < self.zombie_type.spit_range * self.zombie_type.spit_range {
+            self.spit_timer += delta;
+            if self.spit_timer >= self.zombie_type.spit_seconds {
+                self.spit_timer = 0.0;
+                outcome.spat = true;
+            }
+        } else {
+            self.spit_timer = 0.0;
         }
         if self.zombie && self.zombie_countdown < -100.0 && ! self.zombie_exploding {
             self.set_to_explode();
         }
         if self.zombie_exploding && self.zombie_countdown <= 0.0 {
-            finished = true;
+            outcome.finished = true;
         }
 
-        self.workout_my_direction(ground, chase_target, old_pos, original_matrix, distance);
+        self.workout_my_direction(delta, ground, chase_target, old_pos, original_matrix, distance, rng);
 
-        self.turn_around_update(original_matrix);
+        self.turn_around_update(delta, original_matrix);
+
+        if !self.zombie_exploding {
+            self.step_out_of_scenery(ground);
+        }
 
         let ground_height = ground.position_height(self.movement_collision.position.x, self.movement_collision.position.z);
         let ground_height_ahead = ground.position_height(self.movement_collision.position.x, self.movement_collision.position.z);
@@ -168,7 +269,69 @@ impl Passenger {
             }
         }
 
-        return (finished, add_score, zombie_explode);
+        outcome
+    }
+
+    /// Walk round trees and along walls rather than through them.
+    fn step_out_of_scenery(&mut self, ground: &Ground) {
+        for (scenery, tile_xyz) in ground.scenery_within(self.movement_collision.position, self.movement_collision.radius) {
+            if let Some(push) = scenery.push_out(tile_xyz, self.movement_collision.position, self.movement_collision.radius) {
+                self.matrix.w.x += push.x;
+                self.matrix.w.z += push.z;
+                self.update_position();
+            }
+        }
+    }
+
+    /// A car coming at them, humans step out of its lane and zombies go for it.
+    pub fn react_to_car(&mut self, delta: f32, ground: &Ground, car_position: Vector3<f32>, car_facing: Vector3<f32>) {
+        if self.zombie_exploding {
+            return;
+        }
+        let mut from_car = ground.roads.wrapped_delta(car_position, self.movement_collision.position);
+        from_car.y = 0.0;
+        if self.zombie {
+            self.steer_towards(-from_car);
+            return;
+        }
+        let across = from_car - car_facing * from_car.dot(car_facing);
+        let side = if across.magnitude() > 0.001 { across.normalize() } else { vec3(car_facing.z, 0.0, -car_facing.x) };
+        self.matrix.w.x += side.x * DODGE_SPEED * delta;
+        self.matrix.w.z += side.z * DODGE_SPEED * delta;
+        self.update_position();
+    }
+
+    /// Hit by a car, true if it was a human and they are gone. Zombies take it as a hit,
+    /// the same as from the bus.
+    pub fn hit_by_car(&mut self, ground: &Ground, special_effects: &mut SpecialEffects, car_position: Vector3<f32>, rng: &mut GameRng) -> bool {
+        if self.zombie_exploding || self.hit_timer > 0.0 {
+            return false;
+        }
+        if !self.zombie {
+            special_effects.explosion_sized(self.movement_collision.position, HIT_BY_CAR_BLOCKS, rng);
+            return true;
+        }
+        self.hits_left -= 1;
+        self.hit_timer = HIT_AGAIN_SECONDS;
+        if self.hits_left <= 0 {
+            special_effects.explosion_sized(self.movement_collision.position, self.zombie_type.explosion, rng);
+            self.set_to_explode();
+        } else {
+            special_effects.zombie(self.movement_collision.position, rng);
+            self.knock_back(ground, car_position);
+        }
+        false
+    }
+
+    fn knock_back(&mut self, ground: &Ground, from: Vector3<f32>) {
+        let mut away = ground.roads.wrapped_delta(from, self.movement_collision.position);
+        away.y = 0.0;
+        if away.magnitude() > 0.0 {
+            away = away.normalize() * KNOCK_BACK;
+            self.matrix.w.x += away.x;
+            self.matrix.w.z += away.z;
+            self.update_position();
+        }
     }
 
     fn set_to_explode(&mut self) -> bool {
@@ -177,13 +340,13 @@ impl Passenger {
         true
     }
 
-    fn turn_around_update(&mut self, original_matrix: Matrix4<f32>) {
+    fn turn_around_update(&mut self, delta: f32, original_matrix: Matrix4<f32>) {
         let mut diff_angle2 = self.target_angle - self.rotation_angle;
         if diff_angle2 < 0.0 { diff_angle2 = diff_angle2 + 360.0; }
 
         if self.target_angle == self.rotation_angle {
             self.angle_of_rotation = 0.0;
-            self.moves_since_last_change = self.moves_since_last_change + 1;
+            self.moves_since_last_change += delta;
         } else {
             self.animate = 0.0;
             if diff_angle2 > 0.0 && diff_angle2 < 180.0 {
@@ -191,23 +354,32 @@ impl Passenger {
                 self.matrix = original_matrix * self.rotation_y_axis * self.rotation_x_axis;
                 self.update_position();
                 self.angle_of_rotation = 1.0;
-                self.moves_since_last_change = 0;
+                self.moves_since_last_change = 0.0;
             } else {
                 //tickprintln!(format!("2 rotation_angle={} target_angle={} diff_angle1={} diff_angle2={}",self.rotation_angle,self.target_angle,diff_angle1,diff_angle2));
                 self.matrix = original_matrix * self.rotation_y_axis * self.rotation_x_axis;
                 self.update_position();
                 self.angle_of_rotation = -1.0;
-                self.moves_since_last_change = 0;
+                self.moves_since_last_change = 0.0;
             }
         }
     }
 
-    fn workout_my_direction(&mut self, ground: &Ground, chase_target: Vector3<f32>, old_pos: Vector3<f32>, original_matrix: Matrix4<f32>, distance: f32) {
-        if (self.zombie && distance < ZOMBIE_SEE_BUS || distance < HUMAN_SEE_BUS) && self.moves_since_last_change > 60 {
-            let my_degrees = Rad::atan2(old_pos.z - chase_target.z, old_pos.x - chase_target.x);
-            let mut angle_degrees = Deg::from(my_degrees).0.round() - 90.0;
-            if angle_degrees < 0.0 { angle_degrees = angle_degrees + 360.0; }
-            if angle_degrees >= 360.0 { angle_degrees = angle_degrees - 360.0; }
+    #[allow(clippy::too_many_arguments)]
+    fn workout_my_direction(&mut self, delta: f32, ground: &Ground, chase_target: Vector3<f32>, old_pos: Vector3<f32>, original_matrix: Matrix4<f32>, distance: f32, rng: &mut GameRng) {
+        if self.waiting_at.is_some() {
+            return;
+        }
+        let mut to_bus = ground.roads.wrapped_delta(self.movement_collision.position, chase_target);
+        to_bus.y = 0.0;
+        let can_see_bus = to_bus.magnitude() < self.zombie_type.sight;
+        if self.zombie && !self.zombie_exploding && self.zombie_type.leaves_road && can_see_bus {
+            self.steer_towards(to_bus);
+            return;
+        }
+        let zombie_close = self.zombie && can_see_bus && distance < ZOMBIE_SEE_BUS;
+        if (zombie_close || distance < HUMAN_SEE_BUS) && self.moves_since_last_change > SETTLE_SECONDS {
+            let angle_degrees = Passenger::heading_towards(ground.roads.wrapped_delta(old_pos, chase_target));
 
             let rotation = Matrix4::from_angle_y(Deg(angle_degrees));
             let mut off_road = false;
@@ -226,52 +398,115 @@ impl Passenger {
             if off_road == false {
                 self.target_angle = angle_degrees;
                 //println!("Close by {}",angle_degrees);
-            } else {
-                self.do_some_street_smarts(ground, chase_target, original_matrix, old_pos);
-                //println!("failed close by check {}",angle_degrees);
+                return;
             }
-        } else {
-            self.do_some_street_smarts(ground, chase_target, original_matrix, old_pos);
+            //println!("failed close by check {}",angle_degrees);
+        }
+        if self.zombie && !self.zombie_exploding && can_see_bus && self.follow_path(delta, ground, chase_target) {
+            return;
+        }
+        self.do_some_street_smarts(delta, ground, chase_target, original_matrix, old_pos, rng);
+    }
+
+    /// Zombies head for the next node on a path over the road graph, planned again every
+    /// REPLAN_SECONDS to keep up with the bus. False if there is no path to follow.
+    fn follow_path(&mut self, delta: f32, ground: &Ground, chase_target: Vector3<f32>) -> bool {
+        let roads = &ground.roads;
+        let here = self.movement_collision.position;
+
+        self.replan_timer -= delta;
+        if self.replan_timer <= 0.0 {
+            self.replan_timer = REPLAN_SECONDS;
+            self.path = match (roads.nearest_node(here), roads.nearest_node(chase_target)) {
+                (Some(from), Some(to)) if from != to => find_path(roads, from, to).unwrap_or(vec![]),
+                _ => vec![],
+            };
+            // the nearest node can be behind us, no need to walk back to it
+            if self.path.len() >= 2 {
+                let first = roads.nodes[self.path[0]].position;
+                let second = roads.nodes[self.path[1]].position;
+                if roads.wrapped_delta(here, second).magnitude() < roads.wrapped_delta(first, second).magnitude() {
+                    self.path.remove(0);
+                }
+            }
+        }
+
+        while let Some(next) = self.path.first() {
+            // the short way, which might be over the edge of the world
+            let mut to_next = roads.wrapped_delta(here, roads.nodes[*next].position);
+            to_next.y = 0.0;
+            if to_next.magnitude() > WAYPOINT_REACHED {
+                self.steer_towards(to_next);
+                return true;
+            }
+            self.path.remove(0);
         }
+        false
+    }
+
+    fn steer_towards(&mut self, towards: Vector3<f32>) {
+        let heading = Passenger::heading_towards(towards);
+        let mut off_by = (heading - self.target_angle).abs();
+        if off_by > 180.0 { off_by = 360.0 - off_by; }
+        if off_by > WAYPOINT_TURN_DEGREES {
+            self.previous_target_angle = self.target_angle;
+            self.target_angle = heading;
+        }
+    }
+
+    /// Whole degrees to face along towards, 0 is north up -z, 90 is east.
+    fn heading_towards(towards: Vector3<f32>) -> f32 {
+        let my_degrees = Rad::atan2(-towards.z, -towards.x);
+        let mut angle_degrees = Deg::from(my_degrees).0.round() - 90.0;
+        if angle_degrees < 0.0 { angle_degrees += 360.0; }
+        if angle_degrees >= 360.0 { angle_degrees -= 360.0; }
+        angle_degrees
     }
 
-    fn wrap_position_if_needed(&mut self, half_width: f32) {
+    fn wrap_position_if_needed(&mut self, width: f32) {
+        let half_width = width / 2.0;
         if self.movement_collision.position.x < -half_width {
             //println!("b4 x< 0 Reset x={},z={} {}", self.movement_collision.position.x, self.movement_collision.position.z, self.force.w.y);
-            self.flip_reset_the_matrix(1.0, 0.0);
+            self.flip_reset_the_matrix(1.0, 0.0, width);
             //println!("x< 0 Reset x={},z={}", self.movement_collision.position.x, self.movement_collision.position.z);
         } else if self.movement_collision.position.x > half_width {
             //println!("b4 x> Reset x={},z={}  {}", self.movement_collision.position.x, self.movement_collision.position.z, self.force.w.y);
-            self.flip_reset_the_matrix(-1.0, 0.0);
+            self.flip_reset_the_matrix(-1.0, 0.0, width);
             //println!("x> Reset x={},z={}", self.movement_collision.position.x, self.movement_collision.position.z);
         }
         if self.movement_collision.position.z <= -half_width {
             //println!("b4 z<0 Reset x={},z={}  {}", self.movement_collision.position.x, self.movement_collision.position.z, self.force.w.y);
-            self.flip_reset_the_matrix(0.0, 1.0);
+            self.flip_reset_the_matrix(0.0, 1.0, width);
             //println!("z<0 Reset x={},z={}", self.movement_collision.position.x, self.movement_collision.position.z);
         } else if self.movement_collision.position.z >= half_width {
             //println!("b4 z> Reset x={},z={}  {}", self.movement_collision.position.x, self.movement_collision.position.z, self.force.w.y);
-            self.flip_reset_the_matrix(0.0, -1.0);
+            self.flip_reset_the_matrix(0.0, -1.0, width);
         }
     }
 
-    fn do_movement_updates(&mut self, tick: i128, special_effects: &mut SpecialEffects, ) {
+    fn do_movement_updates(&mut self, delta: f32, special_effects: &mut SpecialEffects, rng: &mut GameRng) {
         self.rotation_y_axis = Matrix4::from_angle_y(Deg(-self.angle_of_rotation));
         let mut dir = vec3(0.0, 0.0, self.forward_reverse * self.speed);
         dir = self.applied_rotation.transform_vector(dir) * 0.1;
         self.dir = dir; //self.dir + dir;
+        if self.waiting_at.is_some() {
+            self.dir.set_zero();
+            self.animate = 0.0;
+        }
 
         if ! self.zombie && self.zombie_countdown < 0.0 {
             self.zombie_countdown = -1.0;
-            if self.turning_zombie > 100 {
+            if self.turning_zombie > TURNING_ZOMBIE_SECONDS {
                 self.zombie = true;
-                self.speed = ZOMBIE_SPEED;
+                self.speed = self.zombie_type.speed;
+                self.waiting_at = None;
             } else {
                 self.dir.set_zero();
                 self.animate = 0.0;
-                self.turning_zombie = self.turning_zombie + 1;
-                if self.turning_zombie % 5 == 0 {
-                    special_effects.zombie(self.movement_collision.position);
+                let effects_before = (self.turning_zombie / TURNING_ZOMBIE_EFFECT_SECONDS) as i32;
+                self.turning_zombie += delta;
+                if (self.turning_zombie / TURNING_ZOMBIE_EFFECT_SECONDS) as i32 != effects_before {
+                    special_effects.zombie(self.movement_collision.position, rng);
                 }
             }
         }
@@ -302,7 +537,9 @@ impl Passenger {
         if GRAVITY {
             self.matrix.w.y = self.matrix.w.y - self.gravity;
         }
-        if tick % 60 <= 1 {
+        self.gravity_timer += delta;
+        if self.gravity_timer >= GRAVITY_SECONDS {
+            self.gravity_timer = 0.0;
             if self.gravity <= GRAVITY_MAX {
                 //self.dir = self.dir * 0.9;
                 self.gravity = self.gravity + GRAVITY_ADD;
@@ -310,7 +547,7 @@ impl Passenger {
         }
         self.update_position();
     }
-    fn do_some_street_smarts(&mut self, ground: &Ground, chase_target: Vector3<f32>, original_matrix: Matrix4<f32>, old_pos: Vector3<f32>) {
+    fn do_some_street_smarts(&mut self, delta: f32, ground: &Ground, chase_target: Vector3<f32>, original_matrix: Matrix4<f32>, old_pos: Vector3<f32>, rng: &mut GameRng) {
         let test_direction = self.matrix * self.rotation_y_axis * self.rotation_x_axis * self.ahead_force;
         let okay_forward = self.see_if_road_ahead(ground, test_direction);
 
@@ -328,10 +565,10 @@ impl Passenger {
 
         let current_nsew = vec![north, south, east, west];
 
-        self.nsew_change_clicks = self.nsew_change_clicks + 1;
+        self.nsew_change_clicks += delta;
 
-        if okay_forward && !current_nsew.eq(&self.nsew) && self.moves_since_last_change > 60 {
-            self.nsew_change_clicks = 0;
+        if okay_forward && !current_nsew.eq(&self.nsew) && self.moves_since_last_change > SETTLE_SECONDS {
+            self.nsew_change_clicks = 0.0;
             self.old_nsew = self.nsew.clone();
             //print!("Something changed  old ");
             //Passenger::print_nsew(&self.nsew);
@@ -340,7 +577,7 @@ impl Passenger {
             //println!(" ");
         }
 
-        if self.nsew_change_clicks == 10 {
+        if self.nsew_change_clicks >= NSEW_DECIDE_SECONDS && self.nsew_change_clicks - delta < NSEW_DECIDE_SECONDS {
             let save = self.target_angle;
             if self.old_nsew[2] != self.nsew[2] && self.target_angle != 270.0 && self.movement_collision.position.x < chase_target.x && east {
                 //print!("E ");
@@ -375,7 +612,7 @@ impl Passenger {
                     } else if west {
                         self.target_angle = 270.0
                     } else {
-                        self.stuck_random_move();
+                        self.stuck_random_move(rng);
                     }
                 } else {
                     if west {
@@ -383,7 +620,7 @@ impl Passenger {
                     } else if east {
                         self.target_angle = 90.0
                     } else {
-                        self.stuck_random_move();
+                        self.stuck_random_move(rng);
                     }
                 }
             } else {
@@ -393,7 +630,7 @@ impl Passenger {
                     } else if north {
                         self.target_angle = 0.0
                     } else {
-                        self.stuck_random_move();
+                        self.stuck_random_move(rng);
                     }
                 } else {
                     if north {
@@ -401,7 +638,7 @@ impl Passenger {
                     } else if south {
                         self.target_angle = 180.0
                     } else {
-                        self.stuck_random_move();
+                        self.stuck_random_move(rng);
                     }
                 }
             }
@@ -413,8 +650,7 @@ impl Passenger {
         self.nsew = current_nsew;
     }
 
-    fn stuck_random_move(&mut self) {
-        let mut rng = rand::thread_rng();
+    fn stuck_random_move(&mut self, rng: &mut GameRng) {
         self.target_angle = (rng.gen_range(0, 3) * 90) as f32;
     }
 
@@ -432,9 +668,8 @@ impl Passenger {
 
 
 
-    fn flip_reset_the_matrix(&mut self, x: f32, z: f32) {
-        let width = (SQUARE_COLUMNS) as f32 * SQUARE_SIZE * BY as f32;
-
+    fn flip_reset_the_matrix(&mut self, x: f32, z: f32, width: f32) {
+        
         if !x.is_zero() {
             self.matrix.w.x = self.matrix.w.x + (x * width);
         }
@@ -457,22 +692,56 @@ impl Passenger {
         point = matrix.transform_point(point);
         point2vec(point)
     }
+}
 
-    pub(crate) fn render(&mut self, gl: &gl::Gl, view: &Matrix4<f32>, projection: &Matrix4<f32>, our_shader: u32, offset: Vector3<f32>, tick: i128) {
-        //self.model_instance.matrix = self.matrix;
-        self.model_instance[self.animate as usize].matrix = Matrix4::from_translation(self.movement_collision.position + offset) * self.applied_rotation;
-        if self.zombie {
-            if self.zombie_exploding {
-                if tick % 3 == 0 {
-                    //self.model_instance[self.animate as usize].scale = ZOMBIE_SCALE * self.zombie_countdown * 2.0 ;
-                    self.model_instance[self.animate as usize].scale = ZOMBIE_SCALE;
-                }
-            } else {
-                self.model_instance[self.animate as usize].scale = ZOMBIE_SCALE;
-            }
-            self.model_instance[self.animate as usize].render(gl, &view, &projection, our_shader, true);
-        } else {
-            self.model_instance[self.animate as usize].render(gl, &view, &projection, our_shader, false);
+#[cfg(test)]
+mod tests {
+    use crate::level::{DEFAULT_LEVEL, Level};
+    use crate::world::World;
+
+    use super::*;
+
+    const STEP: f32 = 1.0 / 40.0;
+
+    fn walk(zombie: &mut Passenger, world: &mut World, bus: Vector3<f32>, seconds: f32) {
+        for _ in 0..(seconds / STEP) as usize {
+            zombie.update(STEP, &world.ground, &mut world.special_effects, bus, 0.0, false, &mut world.rng);
         }
     }
-}
\ No newline at end of file
+
+    fn node_near(world: &World, x: f32, z: f32) -> usize {
+        world.ground.roads.nearest_node(vec3(x, 0.0, z)).unwrap()
+    }
+
+    fn distance(world: &World, zombie: &Passenger, to: Vector3<f32>) -> f32 {
+        world.ground.roads.wrapped_delta(zombie.movement_collision.position, to).magnitude()
+    }
+
+    #[test]
+    fn a_zombie_replans_its_way_to_the_bus() {
+        let mut world = World::new(1234, &Level::load(DEFAULT_LEVEL).unwrap());
+        let walker = world.zombie_types.iter().position(|t| t.name == "walker").unwrap();
+        let walker_type = world.zombie_types[walker].clone();
+        let mut start = world.ground.roads.nodes[node_near(&world, -3.2, -6.4)].position;
+        start.y = 0.2;
+        let mut zombie = Passenger::new_zombie(start, walker, &walker_type, &mut world.rng);
+
+        // the bus is east along the roads, round a block
+        let east = node_near(&world, 3.2, -6.4);
+        let bus = world.ground.roads.nodes[east].position;
+        walk(&mut zombie, &mut world, bus, STEP);
+        assert_eq!(zombie.path.last(), Some(&east));
+        let was = distance(&world, &zombie, bus);
+        walk(&mut zombie, &mut world, bus, 10.0);
+        assert!(distance(&world, &zombie, bus) < was - 1.0);
+
+        // then drives off west, the next plan goes after it
+        let west = node_near(&world, -8.0, -6.4);
+        let bus = world.ground.roads.nodes[west].position;
+        walk(&mut zombie, &mut world, bus, REPLAN_SECONDS);
+        assert_eq!(zombie.path.last(), Some(&west));
+        let was = distance(&world, &zombie, bus);
+        walk(&mut zombie, &mut world, bus, 10.0);
+        assert!(distance(&world, &zombie, bus) < was - 1.0);
+    }
+}
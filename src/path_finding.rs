@@ -0,0 +1,109 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use cgmath::InnerSpace;
+
+use crate::road_graph::RoadGraph;
+
+#[derive(PartialEq)]
+struct Open {
+    estimate: f32,
+    node: usize,
+}
+
+impl Eq for Open {}
+
+// BinaryHeap pops the biggest, so smallest estimate has to compare as biggest
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.partial_cmp(&self.estimate).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A* over the road graph, distances are the short way round the wrapped world.
+/// Returns the nodes to visit starting with from and ending with to, or None if to can't be reached.
+pub fn find_path(graph: &RoadGraph, from: usize, to: usize) -> Option<Vec<usize>> {
+    let straight_line = |node: usize| graph.wrapped_delta(graph.nodes[node].position, graph.nodes[to].position).magnitude();
+
+    let mut cost_so_far = vec![f32::MAX; graph.nodes.len()];
+    let mut came_from: Vec<Option<usize>> = vec![None; graph.nodes.len()];
+    let mut open = BinaryHeap::new();
+
+    cost_so_far[from] = 0.0;
+    open.push(Open { estimate: straight_line(from), node: from });
+
+    while let Some(Open { node, .. }) = open.pop() {
+        if node == to {
+            let mut path = vec![to];
+            let mut at = to;
+            while let Some(previous) = came_from[at] {
+                path.push(previous);
+                at = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in graph.neighbours(node).iter() {
+            let length = graph.edge(node, *next).map_or(f32::MAX, |e| e.length);
+            let cost = cost_so_far[node] + length;
+            if cost < cost_so_far[*next] {
+                cost_so_far[*next] = cost;
+                came_from[*next] = Some(node);
+                open.push(Open { estimate: cost + straight_line(*next), node: *next });
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::vec3;
+
+    use super::*;
+
+    // a road all the way round a world 40 wide, and one on its own to the north
+    fn ring() -> RoadGraph {
+        RoadGraph::from_roads(&[
+            [-20.0, -0.3, -12.0, 0.3],
+            [-12.0, -0.3, 0.0, 0.3],
+            [0.0, -0.3, 12.0, 0.3],
+            [12.0, -0.3, 20.0, 0.3],
+            [0.0, 9.7, 5.0, 10.3],
+        ], 40.0)
+    }
+
+    fn node_at(graph: &RoadGraph, x: f32, z: f32) -> usize {
+        graph.nearest_node(vec3(x, 0.0, z)).unwrap()
+    }
+
+    #[test]
+    fn the_short_way_is_over_the_edge_of_the_world() {
+        let graph = ring();
+        let (west, east) = (node_at(&graph, -12.0, 0.0), node_at(&graph, 12.0, 0.0));
+        // 8 + 8 over the edge rather than 12 + 12 through the middle
+        assert_eq!(find_path(&graph, west, east), Some(vec![west, node_at(&graph, 20.0, 0.0), east]));
+        assert_eq!(find_path(&graph, east, west), Some(vec![east, node_at(&graph, -20.0, 0.0), west]));
+    }
+
+    #[test]
+    fn going_nowhere_is_a_path_of_one() {
+        let graph = ring();
+        let middle = node_at(&graph, 0.0, 0.0);
+        assert_eq!(find_path(&graph, middle, middle), Some(vec![middle]));
+    }
+
+    #[test]
+    fn a_road_that_joins_nothing_cannot_be_reached() {
+        let graph = ring();
+        let on_its_own = node_at(&graph, 5.0, 10.0);
+        assert_eq!(find_path(&graph, node_at(&graph, -12.0, 0.0), on_its_own), None);
+        assert_eq!(find_path(&graph, on_its_own, node_at(&graph, 12.0, 0.0)), None);
+    }
+}
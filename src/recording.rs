@@ -0,0 +1,104 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Read, Write};
+
+use crate::world::Input;
+
+// file is MAGIC, the u64 seed, then FRAME_SIZE bytes for each tick. MAGIC changes whenever
+// what the bits mean does, a recording from before won't load
+const MAGIC: &[u8; 4] = b"BZR2";
+const FRAME_SIZE: usize = 6;
+
+/// What was fed into one World::step, enough to play it back exactly.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Frame {
+    pub delta: f32,
+    pub input: Input,
+    pub camera_angle: f32,
+}
+
+impl Frame {
+    fn to_bytes(self) -> [u8; FRAME_SIZE] {
+        let mut bytes = [0u8; FRAME_SIZE];
+        bytes[0..4].copy_from_slice(&self.delta.to_le_bytes());
+        let keys = [self.input.left, self.input.right, self.input.drive, self.input.reverse,
+            self.input.throttle, self.input.brake];
+        for (bit, pressed) in keys.iter().enumerate() {
+            if *pressed {
+                bytes[4] |= 1 << bit;
+            }
+        }
+        // camera only ever points N, E, S or W
+        bytes[5] = (self.camera_angle / 90.0).round() as u8 % 4;
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Frame {
+        let mut delta = [0u8; 4];
+        delta.copy_from_slice(&bytes[0..4]);
+        let pressed = |bit: u8| bytes[4] & (1 << bit) != 0;
+        Frame {
+            delta: f32::from_le_bytes(delta),
+            input: Input {
+                left: pressed(0),
+                right: pressed(1),
+                drive: pressed(2),
+                reverse: pressed(3),
+                throttle: pressed(4),
+                brake: pressed(5),
+            },
+            camera_angle: bytes[5] as f32 * 90.0,
+        }
+    }
+}
+
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+}
+
+impl InputRecorder {
+    pub fn create(filename: &str, seed: u64) -> io::Result<InputRecorder> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&seed.to_le_bytes())?;
+        Ok(InputRecorder { writer })
+    }
+
+    pub fn record(&mut self, frame: &Frame) -> io::Result<()> {
+        self.writer.write_all(&frame.to_bytes())
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+pub struct InputReplay {
+    pub seed: u64,
+    frames: Vec<Frame>,
+    next: usize,
+}
+
+impl InputReplay {
+    pub fn open(filename: &str) -> io::Result<InputReplay> {
+        let mut bytes: Vec<u8> = vec![];
+        File::open(filename)?.read_to_end(&mut bytes)?;
+        if bytes.len() < 12 || &bytes[0..4] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a recording, or is from an older version of the game", filename)));
+        }
+        let mut seed = [0u8; 8];
+        seed.copy_from_slice(&bytes[4..12]);
+        let frames = bytes[12..].chunks_exact(FRAME_SIZE).map(Frame::from_bytes).collect();
+        Ok(InputReplay {
+            seed: u64::from_le_bytes(seed),
+            frames,
+            next: 0,
+        })
+    }
+
+    pub fn next_frame(&mut self) -> Option<Frame> {
+        let frame = self.frames.get(self.next).cloned();
+        self.next += 1;
+        frame
+    }
+}
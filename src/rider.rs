@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+// a delivery is worth this plus up to TIME_BONUS more for a quick trip
+const DELIVERY_SCORE: i32 = 1;
+const TIME_BONUS: f32 = 2.0;
+const QUICK_TRIP_SECONDS: f32 = 60.0;
+pub const RIDER_TURNED_PENALTY: i32 = 2;
+// looks ill this long before turning, time to get them to a hospital
+const SICK_SECONDS: f32 = 15.0;
+// from starting to turn until they go for the driver, stop the bus before then to throw them off
+const TURNING_SECONDS: f32 = 2.5;
+
+/// Someone on the bus, they stop being a Passenger when they get on.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Rider {
+    /// index into the world's bus stops
+    pub destination: usize,
+    pub ride_seconds: f32,
+    /// carried over from the street, below zero and they are turning
+    pub zombie_countdown: f32,
+    pub zombie_type_index: usize,
+    pub cured: bool,
+}
+
+impl Rider {
+    pub fn new(destination: usize, zombie_countdown: f32, zombie_type_index: usize) -> Rider {
+        Rider {
+            destination,
+            ride_seconds: 0.0,
+            zombie_countdown,
+            zombie_type_index,
+            cured: false,
+        }
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.ride_seconds += delta;
+        if !self.cured {
+            self.zombie_countdown -= delta;
+        }
+    }
+
+    pub fn sick(&self) -> bool {
+        !self.cured && self.zombie_countdown < SICK_SECONDS
+    }
+
+    pub fn turning(&self) -> bool {
+        !self.cured && self.zombie_countdown < 0.0
+    }
+
+    pub fn turned(&self) -> bool {
+        !self.cured && self.zombie_countdown < -TURNING_SECONDS
+    }
+
+    pub fn delivery_score(&self) -> i32 {
+        let quickness = (QUICK_TRIP_SECONDS - self.ride_seconds).max(0.0) / QUICK_TRIP_SECONDS;
+        DELIVERY_SCORE + (quickness * TIME_BONUS).ceil() as i32
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::{HashMap, HashSet};
+
+use cgmath::{InnerSpace, vec3, Vector3};
+
+use crate::landscape::Landscape;
+
+// roads closer than this are treated as touching, tiles butt up against each other exactly
+const TOUCHING: f32 = 0.05;
+// nodes closer than this are the same junction
+const MERGE_DISTANCE: f32 = 0.5;
+
+#[derive(Clone, Debug)]
+pub struct RoadNode {
+    pub position: Vector3<f32>,
+    pub neighbours: Vec<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RoadEdge {
+    pub from: usize,
+    pub to: usize,
+    pub length: f32,
+    /// the short way between the two ends goes over the edge of the world
+    #[allow(dead_code)]
+    pub wraps: bool,
+}
+
+/// Road polygons squashed to their bounding box in world coordinates.
+struct RoadRect {
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+}
+
+impl RoadRect {
+    fn long_axis_is_x(&self) -> bool {
+        self.max.x - self.min.x >= self.max.z - self.min.z
+    }
+
+    fn contains(&self, p: Vector3<f32>) -> bool {
+        p.x >= self.min.x - TOUCHING && p.x <= self.max.x + TOUCHING &&
+            p.z >= self.min.z - TOUCHING && p.z <= self.max.z + TOUCHING
+    }
+}
+
+/// Nodes at junctions, tile borders and dead ends with edges along the roads between them,
+/// built once from every polygon whose description says it is road.
+pub struct RoadGraph {
+    pub nodes: Vec<RoadNode>,
+    pub edges: Vec<RoadEdge>,
+    /// smaller node index then bigger to the index in edges
+    edge_index: HashMap<(usize, usize), usize>,
+    width: f32,
+}
+
+impl RoadGraph {
+    pub fn new(land: &[Vec<Landscape>], width: f32) -> RoadGraph {
+        let mut rects: Vec<RoadRect> = vec![];
+        for row in land.iter() {
+            for landscape in row.iter() {
+                for landscape_object in landscape.landscape_objects.iter() {
+                    if !landscape_object.description.contains("road") || landscape_object.vertices.is_empty() {
+                        continue;
+                    }
+                    let mut min = vec3(f32::MAX, 0.0, f32::MAX);
+                    let mut max = vec3(f32::MIN, 0.0, f32::MIN);
+                    for v in landscape_object.vertices.iter() {
+                        let p = landscape.xyz + v;
+                        min.x = min.x.min(p.x);
+                        min.z = min.z.min(p.z);
+                        max.x = max.x.max(p.x);
+                        max.z = max.z.max(p.z);
+                    }
+                    rects.push(RoadRect { min, max });
+                }
+            }
+        }
+        RoadGraph::from_rects(&rects, width)
+    }
+
+    fn from_rects(rects: &[RoadRect], width: f32) -> RoadGraph {
+        let mut graph = RoadGraph { nodes: vec![], edges: vec![], edge_index: HashMap::new(), width };
+
+        // a node at each end of every road
+        for rect in rects.iter() {
+            let centre = (rect.min + rect.max) / 2.0;
+            if rect.long_axis_is_x() {
+                graph.add_node(vec3(rect.min.x, 0.0, centre.z));
+                graph.add_node(vec3(rect.max.x, 0.0, centre.z));
+            } else {
+                graph.add_node(vec3(centre.x, 0.0, rect.min.z));
+                graph.add_node(vec3(centre.x, 0.0, rect.max.z));
+            }
+        }
+        // and one where any two roads meet, including across the wrap
+        for i in 0..rects.len() {
+            for j in (i + 1)..rects.len() {
+                for shift in graph.wrap_shifts().iter() {
+                    let min_x = rects[i].min.x.max(rects[j].min.x + shift.x);
+                    let max_x = rects[i].max.x.min(rects[j].max.x + shift.x);
+                    let min_z = rects[i].min.z.max(rects[j].min.z + shift.z);
+                    let max_z = rects[i].max.z.min(rects[j].max.z + shift.z);
+                    if min_x <= max_x + TOUCHING && min_z <= max_z + TOUCHING {
+                        graph.add_node(vec3((min_x + max_x) / 2.0, 0.0, (min_z + max_z) / 2.0));
+                    }
+                }
+            }
+        }
+
+        // chain together the nodes along the length of each road
+        let mut joined: HashSet<(usize, usize)> = HashSet::new();
+        for rect in rects.iter() {
+            let mut along: Vec<(f32, usize)> = vec![];
+            for (index, node) in graph.nodes.iter().enumerate() {
+                for shift in graph.wrap_shifts().iter() {
+                    let p = node.position - shift;
+                    if rect.contains(p) {
+                        along.push((if rect.long_axis_is_x() { p.x } else { p.z }, index));
+                        break;
+                    }
+                }
+            }
+            along.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            for pair in along.windows(2) {
+                let (a, b) = (pair[0].1, pair[1].1);
+                if a != b && joined.insert((a.min(b), a.max(b))) {
+                    graph.add_edge(a, b);
+                }
+            }
+        }
+        graph
+    }
+
+    /// For tests, each road is min x, min z, max x then max z.
+    #[cfg(test)]
+    pub fn from_roads(roads: &[[f32; 4]], width: f32) -> RoadGraph {
+        let rects: Vec<RoadRect> = roads.iter()
+            .map(|r| RoadRect { min: vec3(r[0], 0.0, r[1]), max: vec3(r[2], 0.0, r[3]) })
+            .collect();
+        RoadGraph::from_rects(&rects, width)
+    }
+
+    fn wrap_shifts(&self) -> [Vector3<f32>; 9] {
+        let w = self.width;
+        [vec3(0.0, 0.0, 0.0),
+            vec3(-w, 0.0, 0.0), vec3(w, 0.0, 0.0), vec3(0.0, 0.0, -w), vec3(0.0, 0.0, w),
+            vec3(-w, 0.0, -w), vec3(-w, 0.0, w), vec3(w, 0.0, -w), vec3(w, 0.0, w)]
+    }
+
+    fn add_node(&mut self, position: Vector3<f32>) -> usize {
+        let position = self.wrap_position(position);
+        for (index, node) in self.nodes.iter().enumerate() {
+            if self.wrapped_delta(node.position, position).magnitude() < MERGE_DISTANCE {
+                return index;
+            }
+        }
+        self.nodes.push(RoadNode { position, neighbours: vec![] });
+        self.nodes.len() - 1
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize) {
+        let length = self.wrapped_delta(self.nodes[from].position, self.nodes[to].position).magnitude();
+        let wraps = self.crosses_wrap_seam(self.nodes[from].position, self.nodes[to].position);
+        self.nodes[from].neighbours.push(to);
+        self.nodes[to].neighbours.push(from);
+        self.edge_index.insert((from.min(to), from.max(to)), self.edges.len());
+        self.edges.push(RoadEdge { from, to, length, wraps });
+    }
+
+    /// Keep x and z inside the world, -width/2 up to width/2.
+    pub fn wrap_position(&self, position: Vector3<f32>) -> Vector3<f32> {
+        let half = self.width / 2.0;
+        let mut p = position;
+        while p.x < -half { p.x += self.width }
+        while p.x >= half { p.x -= self.width }
+        while p.z < -half { p.z += self.width }
+        while p.z >= half { p.z -= self.width }
+        p
+    }
+
+    /// The shortest way from a to b, which may be the long way round in world coordinates.
+    pub fn wrapped_delta(&self, a: Vector3<f32>, b: Vector3<f32>) -> Vector3<f32> {
+        let half = self.width / 2.0;
+        let mut d = b - a;
+        if d.x > half { d.x -= self.width } else if d.x < -half { d.x += self.width }
+        if d.z > half { d.z -= self.width } else if d.z < -half { d.z += self.width }
+        d
+    }
+
+    /// True if going the short way from a to b goes over the edge of the world.
+    pub fn crosses_wrap_seam(&self, a: Vector3<f32>, b: Vector3<f32>) -> bool {
+        let straight = b - a;
+        let shortest = self.wrapped_delta(a, b);
+        (straight - shortest).magnitude() > TOUCHING
+    }
+
+    pub fn nearest_node(&self, position: Vector3<f32>) -> Option<usize> {
+        let mut found: Option<usize> = None;
+        let mut distance = f32::MAX;
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut d = self.wrapped_delta(position, node.position);
+            d.y = 0.0;
+            if d.magnitude2() < distance {
+                distance = d.magnitude2();
+                found = Some(index);
+            }
+        }
+        found
+    }
+
+    pub fn neighbours(&self, node: usize) -> &[usize] {
+        &self.nodes[node].neighbours
+    }
+
+    /// The edge joining two neighbouring nodes, if there is one.
+    pub fn edge(&self, from: usize, to: usize) -> Option<&RoadEdge> {
+        self.edge_index.get(&(from.min(to), from.max(to))).map(|index| &self.edges[*index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: f32 = 40.0;
+
+    fn node_at(graph: &RoadGraph, x: f32, z: f32) -> usize {
+        let index = graph.nearest_node(vec3(x, 0.0, z)).unwrap();
+        assert!(graph.wrapped_delta(graph.nodes[index].position, vec3(x, 0.0, z)).magnitude() < MERGE_DISTANCE,
+                "no node at {},{}", x, z);
+        index
+    }
+
+    #[test]
+    fn roads_meeting_at_a_tile_border_share_a_junction() {
+        // two tiles 10 wide, a road along each from x=-10 to x=0 and x=0 to x=10, and one going
+        // north from where they meet. The ends and overlaps near x=0 all become one node
+        let graph = RoadGraph::from_roads(&[
+            [-10.0, -0.3, 0.0, 0.3],
+            [0.0, -0.3, 10.0, 0.3],
+            [-0.3, -0.3, 0.3, 10.0],
+        ], WIDTH);
+        assert_eq!(graph.nodes.len(), 4);
+        let junction = node_at(&graph, 0.0, 0.0);
+        let west = node_at(&graph, -10.0, 0.0);
+        let east = node_at(&graph, 10.0, 0.0);
+        let north = node_at(&graph, 0.0, 10.0);
+        let mut neighbours = graph.neighbours(junction).to_vec();
+        neighbours.sort();
+        let mut expected = vec![west, east, north];
+        expected.sort();
+        assert_eq!(neighbours, expected);
+        assert_eq!(graph.neighbours(west), &[junction]);
+        assert!((graph.edge(east, junction).unwrap().length - 10.0).abs() < 0.01);
+        assert!(graph.edge(east, west).is_none());
+    }
+
+    #[test]
+    fn nearest_node_looks_across_the_wrap() {
+        let graph = RoadGraph::from_roads(&[[-19.0, -1.0, 10.0, 1.0]], WIDTH);
+        let west = node_at(&graph, -19.0, 0.0);
+        // x=19.5 is 0.5 from -19.0 the other way round the world
+        assert_eq!(graph.nearest_node(vec3(19.5, 0.0, 0.0)), Some(west));
+        assert_eq!(graph.nearest_node(vec3(8.0, 0.0, 3.0)), Some(node_at(&graph, 10.0, 0.0)));
+    }
+
+    #[test]
+    fn a_road_over_the_edge_of_the_world_wraps() {
+        // one road ends at the east edge, the next starts at the west edge
+        let graph = RoadGraph::from_roads(&[
+            [10.0, -1.0, 20.0, 1.0],
+            [-20.0, -1.0, -10.0, 1.0],
+        ], WIDTH);
+        let seam = node_at(&graph, 20.0, 0.0);
+        assert_eq!(seam, node_at(&graph, -20.0, 0.0));
+        let east = node_at(&graph, 10.0, 0.0);
+        let west = node_at(&graph, -10.0, 0.0);
+        assert_eq!(graph.neighbours(seam).len(), 2);
+
+        let over = graph.edge(seam, east).unwrap();
+        let not_over = graph.edge(seam, west).unwrap();
+        // the seam node is kept at x=-20 so only the way east goes over
+        assert!(over.wraps);
+        assert!(!not_over.wraps);
+        assert!((over.length - 10.0).abs() < 0.01);
+        assert!(graph.crosses_wrap_seam(vec3(15.0, 0.0, 0.0), vec3(-15.0, 0.0, 0.0)));
+        assert!(!graph.crosses_wrap_seam(vec3(-15.0, 0.0, 0.0), vec3(-5.0, 0.0, 0.0)));
+    }
+}
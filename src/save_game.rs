@@ -0,0 +1,131 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bus_stop::BusStop;
+use crate::car_main_player::CarMainPlayer;
+#[cfg(not(target_os = "emscripten"))]
+use crate::high_scores::data_dir;
+use crate::level::Level;
+use crate::mission::MissionProgress;
+use crate::passengers::Passenger;
+use crate::rider::Rider;
+use crate::spawn_policy::SavedSpawnPolicy;
+use crate::special_effects::SpecialEffects;
+use crate::traffic::TrafficCar;
+use crate::world::GameRng;
+
+// goes up whenever anything saved changes shape, a save from another version isn't loaded
+pub const SAVE_VERSION: u32 = 2;
+#[cfg(not(target_os = "emscripten"))]
+const SAVE_FILE: &str = "save.json";
+
+/// Everything about a game that changes as it is played, as json. The ground, road graph
+/// and kinds of zombie come from the level again when it is loaded.
+#[derive(Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    pub level: Level,
+    pub bus: CarMainPlayer,
+    pub passengers: Vec<Passenger>,
+    pub traffic: Vec<TrafficCar>,
+    pub special_effects: SpecialEffects,
+    pub bus_stops: Vec<BusStop>,
+    pub riders: Vec<Rider>,
+    pub hospital_timer: f32,
+    pub score: i32,
+    pub lives: i32,
+    pub bus_damage: i32,
+    pub tick: i128,
+    pub game_over: bool,
+    pub level_complete: bool,
+    pub spawn_policy: SavedSpawnPolicy,
+    pub mission: Option<MissionProgress>,
+    pub rng: GameRng,
+    /// time spent driving so far, for the high scores
+    pub seconds_played: f32,
+}
+
+/// Just enough to read the version before trying the rest.
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+impl SaveGame {
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| format!("could not save the game: {}", e))
+    }
+
+    pub fn from_json(text: &str) -> Result<SaveGame, String> {
+        let version: Version = serde_json::from_str(text).map_err(|e| format!("not a saved game: {}", e))?;
+        if version.version != SAVE_VERSION {
+            return Err(format!("saved by version {} of the save format, this game reads version {}", version.version, SAVE_VERSION));
+        }
+        serde_json::from_str(text).map_err(|e| format!("saved game is damaged: {}", e))
+    }
+
+    pub fn write(&self, filename: &str) -> Result<(), String> {
+        let text = self.to_json()?;
+        fs::write(filename, text).map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    pub fn read(filename: &str) -> Result<SaveGame, String> {
+        let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        SaveGame::from_json(&text).map_err(|e| format!("{}: {}", filename, e))
+    }
+}
+
+/// Quitting mid-game saves here, next to the high scores.
+#[cfg(not(target_os = "emscripten"))]
+pub fn default_save_file() -> String {
+    let dir = data_dir();
+    if let Err(e) = fs::create_dir_all(&dir) {
+        println!("Could not make {}: {}", dir.display(), e);
+    }
+    dir.join(SAVE_FILE).to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::level::{DEFAULT_LEVEL, Level};
+    use crate::world::{Input, World};
+
+    use super::*;
+
+    const STEP: f32 = 1.0 / 40.0;
+
+    fn played_for(steps: usize) -> World {
+        let level = Level::load(DEFAULT_LEVEL).unwrap();
+        let mut world = World::new(1234, &level);
+        let input = Input { throttle: true, left: true, ..Input::default() };
+        for _ in 0..steps {
+            world.step(STEP, &input);
+        }
+        world
+    }
+
+    #[test]
+    fn save_then_load_leaves_the_state_unchanged() {
+        let mut world = played_for(400);
+        let saved = world.save_game(10.0).to_json().unwrap();
+        let mut loaded = World::from_save_game(SaveGame::from_json(&saved).unwrap());
+        assert_eq!(loaded.save_game(10.0).to_json().unwrap(), saved);
+
+        // and it carries on just as if it had never stopped
+        let input = Input { throttle: true, right: true, ..Input::default() };
+        for _ in 0..400 {
+            world.step(STEP, &input);
+            loaded.step(STEP, &input);
+        }
+        assert_eq!(loaded.save_game(20.0).to_json().unwrap(), world.save_game(20.0).to_json().unwrap());
+    }
+
+    #[test]
+    fn other_versions_are_not_loaded() {
+        let mut save = played_for(1).save_game(0.0);
+        save.version = SAVE_VERSION + 1;
+        let result = SaveGame::from_json(&save.to_json().unwrap());
+        assert!(result.is_err());
+    }
+}
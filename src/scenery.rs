@@ -1,80 +1,170 @@
-use crate::gl_helper::model::Model;
-use cgmath::{Matrix4, Vector3, vec3};
-use crate::gl_helper::instance_model::ModelInstance;
-use crate::game::{MovementAndCollision, };
-use crate::{gl, };
+use cgmath::{InnerSpace, Matrix4, Vector3, vec3, Zero};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum SceneryType {
     TREE,
     HOUSE,
     OFFICE1,
+    HOSPITAL,
+}
+
+/// What the bus can hit, looking down from above.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Footprint {
+    /// radius of the trunk
+    CIRCLE(f32),
+    /// half the width along x and along z, walls line up with the axes
+    BOX(f32, f32),
 }
 
 pub struct Scenery {
-    pub(crate) model_instance: ModelInstance,
-    pub(crate) movement_collision: MovementAndCollision,
-    matrix:Matrix4<f32>,
+    pub(crate) matrix:Matrix4<f32>,
     pub scenery_type:SceneryType,
     pub position: Vector3<f32>,
+    /// the whole footprint is within this distance of position
     pub collision_radius:f32,
+    pub footprint: Footprint,
+    /// top of the model above the ground
+    pub height: f32,
+    /// middle of the footprint from position, the office model isn't centred on it
+    footprint_offset: Vector3<f32>,
 }
 impl Scenery {
 
-    pub fn setup_tree(gl: &gl::Gl) -> Model {
-        let model = Model::new(gl, "resources/models/tree.obj", "resources/models/tree.png");
-        model
-    }
-    pub fn setup_house(gl: &gl::Gl) -> Model {
-        let model = Model::new(gl, "resources/models/house.obj", "resources/models/house.png");
-        model
-    }
-    pub fn setup_office1(gl: &gl::Gl) -> Model {
-        let model = Model::new(gl, "resources/models/office1.obj", "resources/models/office1.png");
-        model
-    }
-
-
-    pub fn new_tree(gl: &gl::Gl,position:Vector3<f32>,model:&Model) -> Scenery {
-        let model = model.clone();
-        let model_instance = ModelInstance::new(gl,model, 0.01,None);
+    pub fn new_tree(position:Vector3<f32>) -> Scenery {
         Scenery {
-            model_instance,
-            movement_collision:MovementAndCollision::new(0.4, position),
             matrix:Matrix4::from_translation(position),
             scenery_type:SceneryType::TREE,
             position,
-            collision_radius:0.25,
+            collision_radius:0.15,
+            footprint: Footprint::CIRCLE(0.15),
+            height: 0.72,
+            footprint_offset: Vector3::zero(),
         }
     }
-    pub fn new_house(gl: &gl::Gl,position:Vector3<f32>,model:&Model) -> Scenery {
-        let model = model.clone();
-        let model_instance = ModelInstance::new(gl,model, 0.01,None);
+    pub fn new_house(position:Vector3<f32>) -> Scenery {
         Scenery {
-            model_instance,
-            movement_collision:MovementAndCollision::new(0.6, position),
             matrix:Matrix4::from_translation(position + vec3(0.0,0.0,0.0)),
             scenery_type:SceneryType::HOUSE,
             position,
-            collision_radius:1.0,
+            collision_radius:0.52,
+            footprint: Footprint::BOX(0.36, 0.36),
+            height: 0.92,
+            footprint_offset: Vector3::zero(),
         }
     }
-    pub fn new_office1(gl: &gl::Gl,position:Vector3<f32>,model:&Model) -> Scenery {
-        let model = model.clone();
-        let model_instance = ModelInstance::new(gl,model, 0.09,None);
+    pub fn new_office1(position:Vector3<f32>) -> Scenery {
         Scenery {
-            model_instance,
-            movement_collision:MovementAndCollision::new(0.9, position),
             matrix:Matrix4::from_translation(position + vec3(0.125,0.0,0.125)),
             scenery_type:SceneryType::OFFICE1,
             position,
-            collision_radius:1.0,
+            collision_radius:0.9,
+            footprint: Footprint::BOX(0.5, 0.5),
+            height: 2.45,
+            footprint_offset: vec3(0.125, 0.0, 0.125),
+        }
+    }
+    /// the office block painted white, riders are cured if the bus waits outside
+    pub fn new_hospital(position:Vector3<f32>) -> Scenery {
+        Scenery {
+            matrix:Matrix4::from_translation(position + vec3(0.125,0.0,0.125)),
+            scenery_type:SceneryType::HOSPITAL,
+            position,
+            collision_radius:0.9,
+            footprint: Footprint::BOX(0.5, 0.5),
+            height: 2.45,
+            footprint_offset: vec3(0.125, 0.0, 0.125),
+        }
+    }
+
+    /// How far and which way a circle at centre has to move to stop overlapping the footprint,
+    /// None if they don't touch. tile_xyz is the world position of the tile the scenery is on.
+    pub fn push_out(&self, tile_xyz: Vector3<f32>, centre: Vector3<f32>, radius: f32) -> Option<Vector3<f32>> {
+        let middle = tile_xyz + self.position + self.footprint_offset;
+        let from_middle = vec3(centre.x - middle.x, 0.0, centre.z - middle.z);
+        match self.footprint {
+            Footprint::CIRCLE(trunk) => {
+                let distance = from_middle.magnitude();
+                if distance >= trunk + radius {
+                    return None;
+                }
+                let out = if distance > 0.0 { from_middle / distance } else { vec3(1.0, 0.0, 0.0) };
+                Some(out * (trunk + radius - distance))
+            }
+            Footprint::BOX(half_x, half_z) => {
+                let closest = vec3(from_middle.x.max(-half_x).min(half_x), 0.0, from_middle.z.max(-half_z).min(half_z));
+                let outside = from_middle - closest;
+                let distance = outside.magnitude();
+                if distance > 0.0 {
+                    if distance >= radius {
+                        return None;
+                    }
+                    return Some(outside / distance * (radius - distance));
+                }
+                // middle of the circle is inside the walls, out through whichever side is nearest
+                let to_x = half_x - from_middle.x.abs();
+                let to_z = half_z - from_middle.z.abs();
+                if to_x < to_z {
+                    Some(vec3(from_middle.x.signum() * (to_x + radius), 0.0, 0.0))
+                } else {
+                    Some(vec3(0.0, 0.0, from_middle.z.signum() * (to_z + radius)))
+                }
+            }
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    pub(crate) fn render(&mut self, gl: &gl::Gl, view: &Matrix4<f32>, projection: &Matrix4<f32>,our_shader:u32,wrapped_position:Vector3<f32>) {
+    const RADIUS: f32 = 0.15;
 
-        self.model_instance.matrix = self.matrix *Matrix4::from_translation(wrapped_position);
-        self.model_instance.render(gl, &view, &projection,our_shader,false);
+    fn near(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+        (a - b).magnitude() < 0.0001
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn a_circle_is_pushed_straight_away_from_a_trunk() {
+        let tree = Scenery::new_tree(vec3(1.0, 0.0, 1.0));
+        let tile = vec3(10.0, 0.0, 0.0);
+        // 0.2 east and 0.1 south of the trunk, trunk and bus radius are 0.3 together
+        let push = tree.push_out(tile, vec3(11.2, 0.3, 1.1), RADIUS).unwrap();
+        let apart = vec3(0.2, 0.0, 0.1);
+        assert!(near(push, apart.normalize() * (0.3 - apart.magnitude())));
+        assert_eq!(tree.push_out(tile, vec3(11.31, 0.0, 1.0), RADIUS), None);
+        // right on top of it goes east
+        assert!(near(tree.push_out(tile, vec3(11.0, 0.0, 1.0), RADIUS).unwrap(), vec3(0.3, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn a_circle_is_pushed_out_of_a_wall_square_on() {
+        let house = Scenery::new_house(vec3(0.0, 0.0, 0.0));
+        let tile = Vector3::zero();
+        // 0.05 into the east wall, half way along it
+        assert!(near(house.push_out(tile, vec3(0.46, 0.0, 0.2), RADIUS).unwrap(), vec3(0.05, 0.0, 0.0)));
+        // and the south wall
+        assert!(near(house.push_out(tile, vec3(-0.3, 0.0, 0.5), RADIUS).unwrap(), vec3(0.0, 0.0, 0.01)));
+        assert_eq!(house.push_out(tile, vec3(0.52, 0.0, 0.0), RADIUS), None);
+    }
+
+    #[test]
+    fn a_circle_is_pushed_off_a_corner_diagonally() {
+        let house = Scenery::new_house(vec3(0.0, 0.0, 0.0));
+        let push = house.push_out(Vector3::zero(), vec3(0.42, 0.0, 0.42), RADIUS).unwrap();
+        let out = vec3(0.06, 0.0, 0.06);
+        assert!(near(push, out.normalize() * (RADIUS - out.magnitude())));
+        // just clear of the corner, though inside the box around the footprint and radius
+        assert_eq!(house.push_out(Vector3::zero(), vec3(0.48, 0.0, 0.48), RADIUS), None);
+    }
+
+    #[test]
+    fn inside_the_walls_goes_out_the_nearest_side() {
+        let office = Scenery::new_office1(vec3(0.0, 0.0, 0.0));
+        // the footprint is offset to 0.125,0.125 and 0.5 each way, the north wall is 0.1 away
+        let push = office.push_out(Vector3::zero(), vec3(0.2, 0.0, -0.275), RADIUS).unwrap();
+        assert!(near(push, vec3(0.0, 0.0, -0.25)));
+    }
+}
@@ -1,5 +1,5 @@
 use std::borrow::{Cow};
-use std::path::{PathBuf, Path};
+use std::path::Path;
 use sdl2::audio::{AudioCallback, AudioSpecDesired, AudioSpecWAV, AudioCVT, AudioDevice};
 use std::collections::HashMap;
 use sdl2::Sdl;
@@ -157,10 +157,7 @@ fn load_in_file(sdl_context: &Sdl, file_name: &'static str, id: usize, offend: u
         }
     }
 
-    let wav_file: Cow<'static, Path> = match std::env::args().nth(1) {
-        None => Cow::from(Path::new(file_name)),
-        Some(s) => Cow::from(PathBuf::from(s))
-    };
+    let wav_file: Cow<'static, Path> = Cow::from(Path::new(file_name));
     let audio_subsystem = sdl_context.audio().unwrap();
 
     let desired_spec = AudioSpecDesired {
@@ -0,0 +1,182 @@
+use cgmath::{vec3, Vector3};
+
+/// Things in the world bucketed by which square of a uniform grid they are in, so finding
+/// what is near a point only looks at the squares round it. The grid covers the whole
+/// world, -width/2 up to width/2, and wraps round at the edges like the world does.
+/// Ids are the index of each position when it was put in.
+pub struct SpatialGrid {
+    width: f32,
+    cell_size: f32,
+    across: usize,
+    cells: Vec<Vec<usize>>,
+    positions: Vec<Vector3<f32>>,
+}
+
+impl SpatialGrid {
+    /// across squares along each side, a tile is width / tiles wide so use a multiple of the
+    /// tile count to keep squares inside tiles.
+    pub fn new(width: f32, across: usize) -> SpatialGrid {
+        SpatialGrid {
+            width,
+            cell_size: width / across as f32,
+            across,
+            cells: vec![vec![]; across * across],
+            positions: vec![],
+        }
+    }
+
+    /// Throw away what was there and put everything back where it is now.
+    pub fn rebuild<I: Iterator<Item=Vector3<f32>>>(&mut self, positions: I) {
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+        self.positions.clear();
+        for position in positions {
+            self.insert(position);
+        }
+    }
+
+    /// Adds one more, its id is how many were in before it.
+    pub fn insert(&mut self, position: Vector3<f32>) -> usize {
+        let id = self.positions.len();
+        let cell = self.cell_index(self.cell_of(position.x), self.cell_of(position.z));
+        self.cells[cell].push(id);
+        self.positions.push(position);
+        id
+    }
+
+    /// Ids of everything closer than r to position, going the short way round the world.
+    pub fn within(&self, position: Vector3<f32>, r: f32) -> Vec<usize> {
+        let mut found: Vec<usize> = vec![];
+        for cell in self.cells_covering(position.x - r, position.z - r, position.x + r, position.z + r) {
+            for id in self.cells[cell].iter() {
+                let apart = self.wrapped_delta(position, self.positions[*id]);
+                if apart.x * apart.x + apart.z * apart.z < r * r {
+                    found.push(*id);
+                }
+            }
+        }
+        found
+    }
+
+    /// Ids of everything with min.x <= x < max.x and min.z <= z < max.z, for a box inside the world.
+    pub fn inside(&self, min: Vector3<f32>, max: Vector3<f32>) -> Vec<usize> {
+        let mut found: Vec<usize> = vec![];
+        for cell in self.cells_covering(min.x, min.z, max.x, max.z) {
+            for id in self.cells[cell].iter() {
+                let p = self.positions[*id];
+                if p.x >= min.x && p.x < max.x && p.z >= min.z && p.z < max.z {
+                    found.push(*id);
+                }
+            }
+        }
+        found
+    }
+
+    fn cells_covering(&self, min_x: f32, min_z: f32, max_x: f32, max_z: f32) -> Vec<usize> {
+        let across = self.across as i32;
+        let (first_x, last_x) = (self.cell_of_unwrapped(min_x), self.cell_of_unwrapped(max_x));
+        let (first_z, last_z) = (self.cell_of_unwrapped(min_z), self.cell_of_unwrapped(max_z));
+        // bigger than the world and every square gets looked at once
+        let last_x = last_x.min(first_x + across - 1);
+        let last_z = last_z.min(first_z + across - 1);
+        let mut cells: Vec<usize> = vec![];
+        for z in first_z..=last_z {
+            for x in first_x..=last_x {
+                cells.push(self.cell_index(x.rem_euclid(across) as usize, z.rem_euclid(across) as usize));
+            }
+        }
+        cells
+    }
+
+    fn cell_of_unwrapped(&self, v: f32) -> i32 {
+        ((v + self.width / 2.0) / self.cell_size).floor() as i32
+    }
+
+    fn cell_of(&self, v: f32) -> usize {
+        self.cell_of_unwrapped(v).rem_euclid(self.across as i32) as usize
+    }
+
+    fn cell_index(&self, x: usize, z: usize) -> usize {
+        z * self.across + x
+    }
+
+    fn wrapped_delta(&self, a: Vector3<f32>, b: Vector3<f32>) -> Vector3<f32> {
+        let d = b - a;
+        vec3(d.x - self.width * (d.x / self.width).round(), d.y, d.z - self.width * (d.z / self.width).round())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a world 40 wide in squares 5 wide
+    fn grid() -> SpatialGrid {
+        SpatialGrid::new(40.0, 8)
+    }
+
+    fn sorted(mut ids: Vec<usize>) -> Vec<usize> {
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn within_finds_what_is_close() {
+        let mut grid = grid();
+        grid.insert(vec3(1.0, 0.0, 1.0));
+        grid.insert(vec3(4.9, 0.0, 1.0));
+        grid.insert(vec3(5.1, 0.0, 1.0));
+        grid.insert(vec3(1.0, 5.0, 3.0));
+        // height doesn't count
+        assert_eq!(sorted(grid.within(vec3(2.0, 0.0, 1.0), 3.0)), vec![0, 1, 3]);
+        assert_eq!(sorted(grid.within(vec3(5.0, 0.0, 1.0), 0.2)), vec![1, 2]);
+        assert!(grid.within(vec3(-10.0, 0.0, -10.0), 3.0).is_empty());
+    }
+
+    #[test]
+    fn within_looks_over_the_edge_of_the_world() {
+        let mut grid = grid();
+        grid.insert(vec3(19.5, 0.0, 0.0));
+        grid.insert(vec3(0.0, 0.0, -19.8));
+        grid.insert(vec3(19.9, 0.0, 19.9));
+        assert_eq!(grid.within(vec3(-19.5, 0.0, 0.0), 1.5), vec![0]);
+        assert_eq!(grid.within(vec3(0.0, 0.0, 19.8), 0.5), vec![1]);
+        // round both edges to the opposite corner
+        assert_eq!(grid.within(vec3(-19.9, 0.0, -19.9), 0.5), vec![2]);
+        // a circle bigger than the world finds everything once
+        assert_eq!(sorted(grid.within(vec3(0.0, 0.0, 0.0), 100.0)), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn inside_is_min_up_to_but_not_including_max() {
+        let mut grid = grid();
+        grid.insert(vec3(-20.0, 0.0, -20.0));
+        grid.insert(vec3(-12.0, 0.0, -19.0));
+        grid.insert(vec3(-12.1, 0.0, -19.0));
+        grid.insert(vec3(19.9, 0.0, 19.9));
+        let min = vec3(-20.0, 0.0, -20.0);
+        let max = vec3(-12.0, 0.0, -12.0);
+        assert_eq!(sorted(grid.inside(min, max)), vec![0, 2]);
+        // the far corner of the world is only in the last tile, not wrapped into the first
+        assert_eq!(grid.inside(vec3(12.0, 0.0, 12.0), vec3(20.0, 0.0, 20.0)), vec![3]);
+    }
+
+    #[test]
+    fn rebuild_moves_everything() {
+        let mut grid = grid();
+        grid.rebuild(vec![vec3(0.0, 0.0, 0.0), vec3(10.0, 0.0, 10.0)].into_iter());
+        assert_eq!(grid.within(vec3(0.0, 0.0, 0.0), 1.0), vec![0]);
+        // the first has gone right over to the other side and the second round the edge
+        grid.rebuild(vec![vec3(15.0, 0.0, -15.0), vec3(-19.0, 0.0, 10.0)].into_iter());
+        assert!(grid.within(vec3(0.0, 0.0, 0.0), 1.0).is_empty());
+        assert!(grid.within(vec3(10.0, 0.0, 10.0), 1.0).is_empty());
+        assert_eq!(grid.within(vec3(15.0, 0.0, -15.0), 1.0), vec![0]);
+        assert_eq!(grid.within(vec3(19.5, 0.0, 10.0), 2.0), vec![1]);
+        assert_eq!(grid.inside(vec3(-20.0, 0.0, 5.0), vec3(-15.0, 0.0, 15.0)), vec![1]);
+        // and one fewer
+        grid.rebuild(vec![vec3(-19.0, 0.0, 10.0)].into_iter());
+        assert_eq!(grid.within(vec3(-19.0, 0.0, 10.0), 1.0), vec![0]);
+        assert!(grid.within(vec3(15.0, 0.0, -15.0), 1.0).is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+
+use crate::level::{HordeSettings, Level};
+
+/// What the world should add this step, the world does the adding so every mode makes
+/// passengers and zombies the same way.
+#[derive(Clone, PartialEq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Spawn {
+    /// top up the humans, at the stops or on the roads if there are none
+    HUMANS,
+    /// zombies walking in from the edges of tiles, speed is times how fast their kind goes
+    ZOMBIES { count: usize, speed: f32 },
+}
+
+/// Things a spawn policy can tell the front end about.
+#[derive(Clone, PartialEq, Debug)]
+pub enum SpawnEvent {
+    WaveStarted { wave: usize },
+    WaveCleared { wave: usize },
+}
+
+/// Who is about, counted by the world before asking for spawns.
+pub struct Population {
+    pub humans: usize,
+    pub zombies: usize,
+    pub passengers: usize,
+}
+
+/// Decides when new people and zombies arrive, one per game mode.
+pub trait SpawnPolicy {
+    fn spawn(&mut self, delta: f32, population: &Population, level: &Level) -> (Vec<Spawn>, Option<SpawnEvent>);
+    /// a line for the HUD, none if there is nothing to say
+    fn status(&self) -> Option<String>;
+    /// true if reaching the level's target_score doesn't end it
+    fn endless(&self) -> bool;
+    /// where it has got to, for a saved game
+    fn save(&self) -> SavedSpawnPolicy;
+}
+
+/// A spawn policy in a saved game, whichever one it was.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum SavedSpawnPolicy {
+    CLASSIC,
+    HORDE(Horde),
+}
+
+impl SavedSpawnPolicy {
+    pub fn restore(self) -> Box<dyn SpawnPolicy> {
+        match self {
+            SavedSpawnPolicy::CLASSIC => Box::new(Classic),
+            SavedSpawnPolicy::HORDE(horde) => Box::new(horde),
+        }
+    }
+}
+
+/// Keep a few humans about until the lives run out or the target score is reached.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Classic;
+
+impl SpawnPolicy for Classic {
+    fn spawn(&mut self, _delta: f32, population: &Population, level: &Level) -> (Vec<Spawn>, Option<SpawnEvent>) {
+        if (population.passengers == 0 || population.humans <= level.min_humans) && population.passengers <= level.max_passengers {
+            return (vec![Spawn::HUMANS], None);
+        }
+        (vec![], None)
+    }
+
+    fn status(&self) -> Option<String> {
+        None
+    }
+
+    fn endless(&self) -> bool {
+        false
+    }
+
+    fn save(&self) -> SavedSpawnPolicy {
+        SavedSpawnPolicy::CLASSIC
+    }
+}
+
+/// Waves of zombies, each bigger and faster than the one before, with a breather between
+/// them once every zombie is gone. Humans still turn up the classic way.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Horde {
+    settings: HordeSettings,
+    classic: Classic,
+    wave: usize,
+    /// counting down to the next wave, only while the last one has been cleared
+    breather: f32,
+    in_wave: bool,
+}
+
+impl Horde {
+    pub fn new(settings: HordeSettings) -> Horde {
+        let breather = settings.breather_seconds;
+        Horde { settings, classic: Classic, wave: 0, breather, in_wave: false }
+    }
+}
+
+impl SpawnPolicy for Horde {
+    fn spawn(&mut self, delta: f32, population: &Population, level: &Level) -> (Vec<Spawn>, Option<SpawnEvent>) {
+        let (mut spawns, _) = self.classic.spawn(delta, population, level);
+        if self.in_wave {
+            if population.zombies == 0 {
+                self.in_wave = false;
+                self.breather = self.settings.breather_seconds;
+                return (spawns, Some(SpawnEvent::WaveCleared { wave: self.wave }));
+            }
+            return (spawns, None);
+        }
+        self.breather -= delta;
+        if self.breather > 0.0 {
+            return (spawns, None);
+        }
+        self.wave += 1;
+        self.in_wave = true;
+        let more = (self.wave - 1) as f32;
+        spawns.push(Spawn::ZOMBIES {
+            count: self.settings.first_wave + self.settings.wave_increase * (self.wave - 1),
+            speed: 1.0 + self.settings.speed_increase * more,
+        });
+        (spawns, Some(SpawnEvent::WaveStarted { wave: self.wave }))
+    }
+
+    fn status(&self) -> Option<String> {
+        if self.in_wave {
+            Some(format!("wave {}", self.wave))
+        } else {
+            Some(format!("wave {} in {}s", self.wave + 1, self.breather.ceil().max(0.0)))
+        }
+    }
+
+    fn endless(&self) -> bool {
+        true
+    }
+
+    fn save(&self) -> SavedSpawnPolicy {
+        SavedSpawnPolicy::HORDE(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: f32 = 1.0 / 40.0;
+    const HORDE_LEVEL: &str = "resources/levels/horde.toml";
+
+    fn horde() -> (Horde, Level) {
+        let level = Level::load(HORDE_LEVEL).unwrap();
+        (Horde::new(level.horde.clone().unwrap()), level)
+    }
+
+    /// Plenty of humans about so only zombies are asked for.
+    fn population(zombies: usize) -> Population {
+        Population { humans: 5, zombies, passengers: 5 + zombies }
+    }
+
+    /// Steps for that long with that many zombies about, stopping at the first wave.
+    fn run(horde: &mut Horde, level: &Level, zombies: usize, seconds: f32) -> Option<(usize, f32)> {
+        for _ in 0..(seconds / STEP).round() as usize {
+            let (spawns, _) = horde.spawn(STEP, &population(zombies), level);
+            for spawn in spawns {
+                if let Spawn::ZOMBIES { count, speed } = spawn {
+                    return Some((count, speed));
+                }
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn each_wave_is_bigger_and_faster() {
+        let (mut horde, level) = horde();
+        let settings = level.horde.clone().unwrap();
+        let mut waves = vec![];
+        for _ in 0..4 {
+            let wave = run(&mut horde, &level, 0, settings.breather_seconds + 1.0).unwrap();
+            waves.push(wave);
+            // the wave is still about
+            assert_eq!(run(&mut horde, &level, wave.0, 30.0), None);
+        }
+        assert_eq!(waves.iter().map(|w| w.0).collect::<Vec<usize>>(), vec![5, 8, 11, 14]);
+        for (wave, (_, speed)) in waves.iter().enumerate() {
+            assert!((speed - (1.0 + settings.speed_increase * wave as f32)).abs() < 0.0001);
+        }
+        assert_eq!(horde.status(), Some(String::from("wave 4")));
+    }
+
+    /// Steps with no zombies about until the next wave comes.
+    fn seconds_to_next_wave(horde: &mut Horde, level: &Level) -> f32 {
+        let mut seconds = 0.0;
+        while horde.spawn(STEP, &population(0), level).0.is_empty() {
+            seconds += STEP;
+            assert!(seconds < 100.0, "no wave");
+        }
+        seconds
+    }
+
+    #[test]
+    fn a_breather_before_each_wave() {
+        let (mut horde, level) = horde();
+        let breather = level.horde.clone().unwrap().breather_seconds;
+        assert_eq!(horde.status(), Some(String::from("wave 1 in 10s")));
+        assert!((seconds_to_next_wave(&mut horde, &level) - breather).abs() <= STEP * 2.0);
+
+        // nothing more while any of the wave is left, the last one going starts the breather
+        assert_eq!(run(&mut horde, &level, 1, 5.0), None);
+        let (spawns, event) = horde.spawn(STEP, &population(0), &level);
+        assert!(spawns.is_empty());
+        assert_eq!(event, Some(SpawnEvent::WaveCleared { wave: 1 }));
+        assert_eq!(horde.status(), Some(String::from("wave 2 in 10s")));
+        assert!((seconds_to_next_wave(&mut horde, &level) - breather).abs() <= STEP * 2.0);
+        assert_eq!(horde.status(), Some(String::from("wave 2")));
+    }
+
+    #[test]
+    fn humans_still_turn_up_in_a_horde() {
+        let (mut horde, level) = horde();
+        let (spawns, _) = horde.spawn(STEP, &Population { humans: 0, zombies: 0, passengers: 0 }, &level);
+        assert_eq!(spawns, vec![Spawn::HUMANS]);
+    }
+}
@@ -0,0 +1,274 @@
+use std::error::Error;
+use std::fmt;
+
+use csv::{StringRecord, Trim};
+
+pub const IMAGE_SCALE_FACTOR: f32 = 256.0;
+pub const OBJECT_TYPES: [&str; 5] = ["tree", "house", "office1", "hospital", "stop"];
+
+/// One corner of a polygon, x/y/z in squares and u/v in ground.png pixels.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TileVertex {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub u: f32,
+    pub v: f32,
+    pub line: u64,
+}
+
+/// Vertex records up to and including the s,... record that closes them.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TilePolygon {
+    pub description: String,
+    pub vertices: Vec<TileVertex>,
+    pub sides: [bool; 4],
+    pub line: u64,
+}
+
+/// An o,what,x,y,z record, or o,stop,x,y,z,name for a bus stop.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TileObject {
+    pub what: String,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    /// empty for everything but stops
+    pub name: String,
+    pub line: u64,
+}
+
+pub struct TileFile {
+    pub filename: String,
+    pub polygons: Vec<TilePolygon>,
+    pub objects: Vec<TileObject>,
+}
+
+/// Where a tile file went wrong, field is 1 based like a spreadsheet column.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TileError {
+    pub filename: String,
+    pub line: u64,
+    pub field: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for TileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.field {
+            Some(field) => write!(f, "{}:{} field {}: {}", self.filename, self.line, field, self.message),
+            None => write!(f, "{}:{}: {}", self.filename, self.line, self.message),
+        }
+    }
+}
+
+impl Error for TileError {}
+
+fn tile_reader() -> csv::ReaderBuilder {
+    let mut builder = csv::ReaderBuilder::new();
+    builder.has_headers(false).flexible(true).comment(Some(b'#')).trim(Trim::All);
+    builder
+}
+
+pub fn parse_tile(filename: &str) -> Result<TileFile, TileError> {
+    let reader = tile_reader().from_path(filename)
+        .map_err(|e| TileError { filename: filename.to_string(), line: 0, field: None, message: e.to_string() })?;
+    parse_records(filename, reader)
+}
+
+fn parse_records<R: std::io::Read>(filename: &str, reader: csv::Reader<R>) -> Result<TileFile, TileError> {
+    let mut tile = TileFile {
+        filename: filename.to_string(),
+        polygons: vec![],
+        objects: vec![],
+    };
+    let mut polygon = TilePolygon { description: String::new(), vertices: vec![], sides: [false; 4], line: 0 };
+
+    for record in reader.into_records() {
+        let record = record.map_err(|e| {
+            let line = e.position().map_or(0, |p| p.line());
+            TileError { filename: filename.to_string(), line, field: None, message: e.to_string() }
+        })?;
+        let line = record.position().map_or(0, |p| p.line());
+        let error = |field: Option<usize>, message: String| TileError { filename: filename.to_string(), line, field, message };
+
+        if record.is_empty() || (record.len() == 1 && record[0].is_empty()) {
+            continue;
+        }
+        match &record[0] {
+            "o" => {
+                let is_stop = record.len() > 1 && &record[1] == "stop";
+                expect_fields(&record, if is_stop { 6 } else { 5 }, &error)?;
+                tile.objects.push(TileObject {
+                    what: record[1].to_string(),
+                    x: number(&record, 2, &error)?,
+                    y: number(&record, 3, &error)?,
+                    z: number(&record, 4, &error)?,
+                    name: if is_stop { record[5].to_string() } else { String::new() },
+                    line,
+                });
+            }
+            "d" => {
+                expect_fields(&record, 2, &error)?;
+                polygon.description = record[1].to_string();
+            }
+            "s" => {
+                expect_fields(&record, 5, &error)?;
+                if polygon.vertices.is_empty() {
+                    return Err(error(None, "s record without any vertices before it".to_string()));
+                }
+                for side in 0..4 {
+                    polygon.sides[side] = match &record[side + 1] {
+                        "0" => false,
+                        "1" => true,
+                        other => return Err(error(Some(side + 2), format!("side should be 0 or 1 not '{}'", other))),
+                    };
+                }
+                tile.polygons.push(polygon.clone());
+                polygon = TilePolygon { description: String::new(), vertices: vec![], sides: [false; 4], line: 0 };
+            }
+            _ => {
+                expect_fields(&record, 5, &error)?;
+                if polygon.vertices.is_empty() {
+                    polygon.line = line;
+                }
+                polygon.vertices.push(TileVertex {
+                    x: number(&record, 0, &error)?,
+                    y: number(&record, 1, &error)?,
+                    z: number(&record, 2, &error)?,
+                    u: number(&record, 3, &error)?,
+                    v: number(&record, 4, &error)?,
+                    line,
+                });
+            }
+        }
+    }
+    if !polygon.vertices.is_empty() {
+        return Err(TileError {
+            filename: filename.to_string(),
+            line: polygon.line,
+            field: None,
+            message: "polygon is not closed with an s record".to_string(),
+        });
+    }
+    Ok(tile)
+}
+
+fn expect_fields<F: Fn(Option<usize>, String) -> TileError>(record: &StringRecord, wanted: usize, error: &F) -> Result<(), TileError> {
+    if record.len() != wanted {
+        return Err(error(None, format!("expected {} fields for a '{}' record but found {}", wanted, &record[0], record.len())));
+    }
+    Ok(())
+}
+
+fn number<F: Fn(Option<usize>, String) -> TileError>(record: &StringRecord, index: usize, error: &F) -> Result<f32, TileError> {
+    record[index].parse::<f32>().map_err(|_| error(Some(index + 1), format!("'{}' is not a number", &record[index])))
+}
+
+/// Problems that parse fine but would draw wrong, every one is reported not just the first.
+pub fn check_tile(tile: &TileFile) -> Vec<TileError> {
+    let mut problems: Vec<TileError> = vec![];
+    let problem = |line: u64, field: Option<usize>, message: String| TileError { filename: tile.filename.clone(), line, field, message };
+
+    for polygon in tile.polygons.iter() {
+        if polygon.vertices.len() != 4 {
+            problems.push(problem(polygon.line, None, format!("polygon has {} vertices, only quads are drawn properly", polygon.vertices.len())));
+        }
+        for vertex in polygon.vertices.iter() {
+            if vertex.u < 0.0 || vertex.u > IMAGE_SCALE_FACTOR {
+                problems.push(problem(vertex.line, Some(4), format!("u {} is outside 0 to {}", vertex.u, IMAGE_SCALE_FACTOR)));
+            }
+            if vertex.v < 0.0 || vertex.v > IMAGE_SCALE_FACTOR {
+                problems.push(problem(vertex.line, Some(5), format!("v {} is outside 0 to {}", vertex.v, IMAGE_SCALE_FACTOR)));
+            }
+        }
+    }
+    for object in tile.objects.iter() {
+        if !OBJECT_TYPES.contains(&object.what.as_str()) {
+            problems.push(problem(object.line, Some(2), format!("unknown object type '{}'", object.what)));
+        }
+        if object.what == "stop" {
+            if object.name.is_empty() {
+                problems.push(problem(object.line, Some(6), "stop has no name".to_string()));
+            }
+            let on_road = tile.polygons.iter()
+                .any(|p| p.description.contains("road") && polygon_contains_x_z(object.x, object.z, &p.vertices));
+            if !on_road {
+                problems.push(problem(object.line, None, format!("stop at {},{} is not on a road", object.x, object.z)));
+            }
+        }
+    }
+    problems
+}
+
+fn polygon_contains_x_z(x: f32, z: f32, vertices: &[TileVertex]) -> bool {
+    let min_x = vertices.iter().fold(f32::MAX, |m, v| m.min(v.x));
+    let max_x = vertices.iter().fold(f32::MIN, |m, v| m.max(v.x));
+    let min_z = vertices.iter().fold(f32::MAX, |m, v| m.min(v.z));
+    let max_z = vertices.iter().fold(f32::MIN, |m, v| m.max(v.z));
+    // every polygon is a quad lined up with the squares, check_tile complains if not
+    x >= min_x && x <= max_x && z >= min_z && z <= max_z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<TileFile, TileError> {
+        parse_records("test.txt", tile_reader().from_reader(text.as_bytes()))
+    }
+
+    const ROAD: &str = "d,road\n0,0,0,0,0\n1,0,0,10,0\n1,0,1,10,10\n0,0,1,0,10\ns,1,0,1,0\n";
+
+    #[test]
+    fn a_good_tile_parses() {
+        let tile = parse(&format!("# a comment\n{}o,tree,0.5,0,0.5\no,stop,0.5,0,0.5,High St\n", ROAD)).unwrap();
+        assert_eq!(tile.polygons.len(), 1);
+        assert_eq!(tile.polygons[0].description, "road");
+        assert_eq!(tile.polygons[0].line, 3);
+        assert_eq!(tile.polygons[0].sides, [true, false, true, false]);
+        assert_eq!(tile.objects.len(), 2);
+        assert_eq!(tile.objects[1].name, "High St");
+        assert!(check_tile(&tile).is_empty());
+    }
+
+    #[test]
+    fn a_bad_number_gives_its_line_and_field() {
+        let error = parse("d,road\n0,0,0,0,0\n1,0,zero,10,0\n").err().unwrap();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.field, Some(3));
+        assert_eq!(error.to_string(), "test.txt:3 field 3: 'zero' is not a number");
+    }
+
+    #[test]
+    fn an_unknown_record_type_is_rejected() {
+        let error = parse("q,0,0,0,0\n").err().unwrap();
+        assert_eq!((error.line, error.field), (1, Some(1)));
+        let error = parse("q,road\n").err().unwrap();
+        assert_eq!((error.line, error.field), (1, None));
+    }
+
+    #[test]
+    fn sides_are_0_or_1() {
+        let error = parse("0,0,0,0,0\ns,1,0,2,0\n").err().unwrap();
+        assert_eq!((error.line, error.field), (2, Some(4)));
+    }
+
+    #[test]
+    fn a_polygon_has_to_be_closed() {
+        let error = parse("d,road\n0,0,0,0,0\n1,0,0,10,0\n").err().unwrap();
+        assert_eq!((error.line, error.field), (2, None));
+    }
+
+    #[test]
+    fn check_finds_what_would_draw_wrong() {
+        let tile = parse("d,grass\n0,0,0,0,0\n1,0,0,300,0\n1,0,1,10,-1\ns,0,0,0,0\no,car,0.5,0,0.5\no,stop,0.5,0,0.5,Nowhere\n").unwrap();
+        let problems: Vec<(u64, Option<usize>)> = check_tile(&tile).iter().map(|p| (p.line, p.field)).collect();
+        assert_eq!(problems, vec![
+            // only 3 vertices, u too big, v below 0
+            (2, None), (3, Some(4)), (4, Some(5)),
+            // no such object, and a stop that isn't on a road
+            (6, Some(2)), (7, None),
+        ]);
+    }
+}
@@ -0,0 +1,295 @@
+use cgmath::{InnerSpace, Matrix4, Rad, vec3, Vector3};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::ground::Ground;
+use crate::road_graph::RoadGraph;
+use crate::world::{GameRng, MovementAndCollision};
+
+// world units per second, a bit slower than the bus can go
+const CRUISE_SPEED: f32 = 1.5;
+const ACCELERATION: f32 = 1.0;
+const BRAKING: f32 = 4.0;
+// driving on the left, this far from the middle of the road
+const LANE_OFFSET: f32 = 0.4;
+// road graph edges can run along the side of a road, it is looked for this far either side
+const ROAD_SEARCH: f32 = 1.2;
+const ROAD_SEARCH_STEP: f32 = 0.1;
+// half the width of a lane, anything further to the side than this isn't in the way
+const LANE_HALF_WIDTH: f32 = 0.3;
+// stop this far before a junction and wait there this long
+const STOP_LINE: f32 = 0.9;
+const JUNCTION_SECONDS: f32 = 1.0;
+// slow down for anything in the lane closer than this
+const FOLLOW_DISTANCE: f32 = 0.8;
+// give up waiting for whatever is in the way after this long and go anyway
+const BLOCKED_SECONDS: f32 = 4.0;
+// how long a car sits still after hitting something
+const CRASHED_SECONDS: f32 = 3.0;
+// how much faster than cruising a car can cut across to its lane on a corner
+const CORNER_CATCH_UP: f32 = 1.5;
+// edges a car can go along in one step, in case of any with no length at all
+const MAX_HOPS: usize = 4;
+// the car model goes this far below its origin
+const MODEL_HEIGHT: f32 = 0.04;
+pub const CAR_RADIUS: f32 = 0.15;
+pub const CAR_SCALE: f32 = 0.01;
+
+/// A car driving round the roads on its own, along the road graph one edge at a time.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrafficCar {
+    pub movement_collision: MovementAndCollision,
+    /// flat and length 1.0, the way the car points
+    pub facing: Vector3<f32>,
+    pub speed: f32,
+    from: usize,
+    to: usize,
+    /// how far from the from node along the edge
+    along: f32,
+    /// how far left of the edge the middle of the road is
+    middle: f32,
+    waited: f32,
+    blocked: f32,
+    crashed: f32,
+}
+
+impl TrafficCar {
+    /// At the start of the edge from one node to its neighbour to.
+    pub fn new(ground: &Ground, from: usize, to: usize) -> TrafficCar {
+        let roads = &ground.roads;
+        let mut car = TrafficCar {
+            movement_collision: MovementAndCollision::new(CAR_RADIUS, roads.nodes[from].position),
+            facing: vec3(0.0, 0.0, -1.0),
+            speed: 0.0,
+            from,
+            to,
+            along: 0.0,
+            middle: 0.0,
+            waited: 0.0,
+            blocked: 0.0,
+            crashed: 0.0,
+        };
+        car.facing = car.edge_direction(roads);
+        car.find_middle_of_road(ground);
+        car.movement_collision.position = car.lane_point(roads);
+        car.movement_collision.previous_position = car.movement_collision.position;
+        car
+    }
+
+    fn edge_direction(&self, roads: &RoadGraph) -> Vector3<f32> {
+        let mut d = roads.wrapped_delta(roads.nodes[self.from].position, roads.nodes[self.to].position);
+        d.y = 0.0;
+        if d.magnitude() > 0.0 { d.normalize() } else { self.facing }
+    }
+
+    fn edge_length(&self, roads: &RoadGraph) -> f32 {
+        roads.wrapped_delta(roads.nodes[self.from].position, roads.nodes[self.to].position).magnitude()
+    }
+
+    /// Where the car should be, on the left hand side of the road.
+    fn lane_point(&self, roads: &RoadGraph) -> Vector3<f32> {
+        let d = self.edge_direction(roads);
+        let left = vec3(d.z, 0.0, -d.x);
+        roads.wrap_position(roads.nodes[self.from].position + d * self.along + left * (self.middle + LANE_OFFSET))
+    }
+
+    /// Looks across the road from the edge for both sides of it. Left as it was in a junction,
+    /// where the road carries on past the search either side.
+    fn find_middle_of_road(&mut self, ground: &Ground) {
+        let roads = &ground.roads;
+        let d = self.edge_direction(roads);
+        let left = vec3(d.z, 0.0, -d.x);
+        let here = roads.nodes[self.from].position + d * self.along;
+        let on_road = |across: f32| {
+            let p = here + left * across;
+            ground.object_at(p.x, p.z).is_some_and(|o| o.description.contains("road"))
+        };
+        let steps = (ROAD_SEARCH / ROAD_SEARCH_STEP) as i32;
+        let start = (-steps..=steps).map(|s| s as f32 * ROAD_SEARCH_STEP)
+            .filter(|a| on_road(*a))
+            .min_by(|a, b| a.abs().partial_cmp(&b.abs()).unwrap());
+        let start = match start {
+            Some(start) => start,
+            None => return,
+        };
+        let mut right_side = start;
+        while on_road(right_side - ROAD_SEARCH_STEP) {
+            right_side -= ROAD_SEARCH_STEP;
+            if right_side < -ROAD_SEARCH {
+                return;
+            }
+        }
+        let mut left_side = start;
+        while on_road(left_side + ROAD_SEARCH_STEP) {
+            left_side += ROAD_SEARCH_STEP;
+            if left_side > ROAD_SEARCH {
+                return;
+            }
+        }
+        self.middle = (left_side + right_side) / 2.0;
+    }
+
+    /// True if p is in this car's lane a little way in front of it.
+    pub fn sees_ahead(&self, roads: &RoadGraph, p: Vector3<f32>) -> bool {
+        let mut d = roads.wrapped_delta(self.movement_collision.position, p);
+        d.y = 0.0;
+        let ahead = d.dot(self.facing);
+        let side = (d - self.facing * ahead).magnitude();
+        ahead > 0.0 && ahead < FOLLOW_DISTANCE && side < LANE_HALF_WIDTH
+    }
+
+    /// Something ran into it, it stops for a while.
+    pub fn crash(&mut self) {
+        self.crashed = CRASHED_SECONDS;
+        self.speed = 0.0;
+    }
+
+    /// blocked is true when the bus or another car is in the way.
+    pub fn update(&mut self, delta: f32, ground: &Ground, blocked: bool, rng: &mut GameRng) {
+        let roads = &ground.roads;
+        if self.crashed > 0.0 {
+            self.crashed -= delta;
+            return;
+        }
+        self.blocked = if blocked { self.blocked + delta } else { 0.0 };
+
+        let length = self.edge_length(roads);
+        let junction = roads.neighbours(self.to).len() > 2;
+        let to_stop_line = length - STOP_LINE - self.along;
+        let mut want = CRUISE_SPEED;
+        if blocked && self.blocked < BLOCKED_SECONDS {
+            want = 0.0;
+        } else if junction && self.waited < JUNCTION_SECONDS && to_stop_line <= self.speed * self.speed / (2.0 * BRAKING) + 0.05 {
+            want = 0.0;
+            if self.speed < 0.05 {
+                self.waited += delta;
+            }
+        }
+        if self.speed < want {
+            self.speed = (self.speed + ACCELERATION * delta).min(want);
+        } else {
+            self.speed = (self.speed - BRAKING * delta).max(want);
+        }
+
+        self.along += self.speed * delta;
+        let mut hops = 0;
+        let mut length = length;
+        while self.along >= length && hops < MAX_HOPS {
+            // straight on at a bend or tile edge, any way but back at a junction, turn round at a dead end
+            let choices: Vec<usize> = roads.neighbours(self.to).iter().cloned().filter(|n| *n != self.from).collect();
+            let next = if choices.is_empty() { self.from } else { choices[rng.gen_range(0, choices.len())] };
+            self.along -= length;
+            self.from = self.to;
+            self.to = next;
+            self.waited = 0.0;
+            length = self.edge_length(roads);
+            hops += 1;
+        }
+
+        self.find_middle_of_road(ground);
+        // cut across to the lane rather than jumping, so corners are rounded off
+        let mut step = roads.wrapped_delta(self.movement_collision.position, self.lane_point(roads));
+        step.y = 0.0;
+        let most = CRUISE_SPEED * CORNER_CATCH_UP * delta;
+        if step.magnitude() > most {
+            step = step.normalize() * most;
+        }
+        if step.magnitude() > 0.001 {
+            self.facing = step.normalize();
+        }
+        let mut position = roads.wrap_position(self.movement_collision.position + step);
+        position.y = ground.position_height(position.x, position.z) + MODEL_HEIGHT;
+        self.movement_collision.position = position;
+    }
+
+    /// Turned to face the way it is going and moved to position.
+    pub fn matrix(&self, position: Vector3<f32>) -> Matrix4<f32> {
+        Matrix4::from_translation(position) * Matrix4::from_angle_y(Rad(f32::atan2(-self.facing.x, -self.facing.z)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::level::{DEFAULT_LEVEL, Level};
+    use crate::world::World;
+
+    use super::*;
+
+    const STEP: f32 = 1.0 / 40.0;
+
+    fn world() -> World {
+        World::new(1234, &Level::load(DEFAULT_LEVEL).unwrap())
+    }
+
+    /// How far p is from the nearest point on the edge the car is on.
+    fn off_edge(roads: &RoadGraph, car: &TrafficCar) -> f32 {
+        let from = roads.nodes[car.from].position;
+        let along_edge = roads.wrapped_delta(from, roads.nodes[car.to].position);
+        let mut to_car = roads.wrapped_delta(from, car.movement_collision.position);
+        to_car.y = 0.0;
+        let t = (to_car.dot(along_edge) / along_edge.magnitude2()).clamp(0.0, 1.0);
+        (to_car - along_edge * t).magnitude()
+    }
+
+    #[test]
+    fn cars_keep_to_the_road_graph() {
+        let world = world();
+        let ground = &world.ground;
+        let mut rng = GameRng::seed_from_u64(1);
+        for edge in ground.roads.edges.iter() {
+            let mut car = TrafficCar::new(ground, edge.from, edge.to);
+            for _ in 0..(10.0 / STEP) as usize {
+                car.update(STEP, ground, false, &mut rng);
+                assert!(ground.roads.edge(car.from, car.to).is_some());
+                assert!(car.along >= 0.0 && car.along <= car.edge_length(&ground.roads));
+                // in its lane either side of the middle of the road
+                assert!(off_edge(&ground.roads, &car) <= LANE_OFFSET + ROAD_SEARCH);
+            }
+        }
+    }
+
+    #[test]
+    fn cars_stop_at_junctions() {
+        let world = world();
+        let ground = &world.ground;
+        let roads = &ground.roads;
+        let mut rng = GameRng::seed_from_u64(1);
+        let edge = roads.edges.iter()
+            .find(|e| roads.neighbours(e.to).len() > 2 && e.length > STOP_LINE + 2.0)
+            .unwrap();
+        let mut car = TrafficCar::new(ground, edge.from, edge.to);
+        let mut stopped = 0.0;
+        let mut stopped_at = 0.0;
+        while car.from == edge.from {
+            car.update(STEP, ground, false, &mut rng);
+            if car.along > 0.0 && car.speed == 0.0 {
+                stopped += STEP;
+                stopped_at = car.along;
+            }
+            assert!(car.speed <= CRUISE_SPEED);
+        }
+        assert_eq!(car.from, edge.to);
+        assert!((JUNCTION_SECONDS - STEP..JUNCTION_SECONDS + 0.5).contains(&stopped), "stopped for {}", stopped);
+        assert!((stopped_at - (edge.length - STOP_LINE)).abs() < 0.1, "stopped at {} of {}", stopped_at, edge.length);
+    }
+
+    #[test]
+    fn cars_wait_for_what_is_in_the_way_then_go_round() {
+        let world = world();
+        let ground = &world.ground;
+        let mut rng = GameRng::seed_from_u64(1);
+        let edge = &ground.roads.edges[0];
+        let mut car = TrafficCar::new(ground, edge.from, edge.to);
+        car.speed = CRUISE_SPEED;
+        for _ in 0..(2.0 / STEP) as usize {
+            car.update(STEP, ground, true, &mut rng);
+        }
+        assert_eq!(car.speed, 0.0);
+        for _ in 0..(BLOCKED_SECONDS / STEP) as usize {
+            car.update(STEP, ground, true, &mut rng);
+        }
+        assert!(car.speed > 0.0);
+    }
+}
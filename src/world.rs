@@ -0,0 +1,1156 @@
+use std::ops::{Add, Div};
+
+use cgmath::{InnerSpace, vec3, Vector3, Zero};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use crate::bus_stop::{BusStop, MAX_QUEUE, PICKUP_SPEED};
+use crate::bus_tuning::{BUS_TUNINGS_FILE, load_bus_tunings};
+use crate::car_main_player::{CarMainPlayer, CRASH_SPEED};
+use crate::ground::{Ground, GRID_SQUARES_PER_TILE};
+use crate::level::Level;
+use crate::mission::{Mission, MissionProgress, MissionState, MissionStep};
+use crate::passengers::{NEAR_BUS, Passenger};
+use crate::rider::{Rider, RIDER_TURNED_PENALTY};
+use crate::save_game::{SAVE_VERSION, SaveGame};
+use crate::scenery::SceneryType;
+use crate::spatial_grid::SpatialGrid;
+use crate::spawn_policy::{Classic, Horde, Population, Spawn, SpawnEvent, SpawnPolicy};
+use crate::special_effects::SpecialEffects;
+use crate::traffic::{CAR_RADIUS, TrafficCar};
+use crate::zombie_type::{load_zombie_types, ZOMBIE_TYPES_FILE, ZombieType};
+
+pub const GROUND: f32 = 0.01;
+// anything moving further than this in one step has wrapped round the world
+const WRAP_JUMP: f32 = 1.0;
+// the bus has to wait this close to a hospital, and this long for each rider cured
+const HOSPITAL_RADIUS: f32 = 2.0;
+const CURE_SECONDS: f32 = 1.0;
+// the bus is wrecked at MAX_BUS_DAMAGE, costing a life
+const MAX_BUS_DAMAGE: i32 = 100;
+const RUN_OVER_DAMAGE: i32 = 20;
+const SPIT_DAMAGE: i32 = 25;
+// hitting a wall head on at full speed, about 3.0, is most of the way to wrecking the bus
+const CRASH_DAMAGE_PER_SPEED: f32 = 20.0;
+const RUN_OVER_SCORE: i32 = 1;
+// far enough from the bus that a thrown off zombie doesn't get straight back at it
+const EJECT_DISTANCE: f32 = 0.6;
+// horde zombies don't walk in from a tile edge closer to the bus than this
+const HORDE_CLEAR_OF_BUS: f32 = 6.0;
+// road graph nodes this close to the line between two tiles are on the edge
+const ON_TILE_EDGE: f32 = 0.1;
+// cars start at least this far from the bus
+const TRAFFIC_CLEAR_OF_BUS: f32 = 3.0;
+// a car has to be going this fast to hurt anyone, and people take notice of it this close
+const CAR_HIT_SPEED: f32 = 0.5;
+const CAR_NOTICE: f32 = 1.0;
+// about the top of the bus, blocks flying higher than this go over it
+const BUS_HEIGHT: f32 = 0.4;
+
+static mut GLOBAL_ID: u128 = 0;
+
+fn get_next_id() -> u128 {
+    unsafe {
+        GLOBAL_ID += 1;
+        GLOBAL_ID
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MovementAndCollision {
+    #[serde(skip, default = "get_next_id")]
+    pub id: u128,
+    pub radius: f32,
+    pub position: Vector3<f32>,
+    pub previous_position: Vector3<f32>,
+    pub been_hit: bool,
+    pub moved: bool,
+}
+
+impl Default for MovementAndCollision {
+    fn default() -> Self {
+        MovementAndCollision {
+            id: get_next_id(),
+            radius: 0.0,
+            position: Vector3::zero(),
+            previous_position: Vector3::zero(),
+            been_hit: false,
+            moved: false,
+        }
+    }
+}
+
+
+impl MovementAndCollision {
+    pub fn new(radius: f32, position: Vector3<f32>) -> MovementAndCollision {
+        MovementAndCollision {
+            radius,
+            position,
+            previous_position: position,
+            been_hit: false,
+            moved: false,
+            ..MovementAndCollision::default()
+        }
+    }
+
+    /// Where to draw this between the last step and the current one, alpha 0.0 to 1.0.
+    pub fn interpolated_position(&self, alpha: f32) -> Vector3<f32> {
+        let moved = self.position - self.previous_position;
+        if moved.magnitude() > WRAP_JUMP {
+            return self.position;
+        }
+        self.previous_position + moved * alpha
+    }
+}
+
+/// The one random number generator for the game, seeded so a run can be repeated.
+pub type GameRng = Pcg32;
+
+pub(crate) trait Update {
+    fn update(&mut self, delta: f32, ground: &Ground, rng: &mut GameRng);
+}
+
+/// The driving controls for one step, whatever produced them.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Input {
+    pub left: bool,
+    pub right: bool,
+    /// select forward gear
+    pub drive: bool,
+    /// select reverse gear
+    pub reverse: bool,
+    pub throttle: bool,
+    pub brake: bool,
+}
+
+/// Things that happened during a step that the front end may want to show or play.
+#[derive(Clone, PartialEq, Debug)]
+pub enum WorldEvent {
+    PassengerPickedUp,
+    PassengerDelivered { score: i32 },
+    RiderTurning,
+    RiderEjected,
+    RiderTurned,
+    RiderCured,
+    ZombieExploded,
+    ZombieSpat,
+    ZombieRunOver,
+    BusWrecked,
+    /// impact_speed is how much of the bus's speed went into it, in world units per second
+    BusHitScenery { scenery_type: SceneryType, impact_speed: f32 },
+    /// impact_speed is the bus's and the car's speed into each other
+    BusHitCar { impact_speed: f32 },
+    HumanHitByCar,
+    OffRoadWarning,
+    OffRoadTooLong,
+    LevelComplete,
+    WaveStarted { wave: usize },
+    WaveCleared { wave: usize },
+    MissionSucceeded,
+    MissionFailed { reason: String },
+}
+
+/// All of the game state, no OpenGL or SDL in here so it can be stepped without a window.
+pub struct World {
+    pub ground: Ground,
+    pub bus: CarMainPlayer,
+    pub passengers: Vec<Passenger>,
+    pub traffic: Vec<TrafficCar>,
+    /// where every passenger is, ids are indexes into passengers, rebuilt whenever they move or come and go
+    pub passenger_grid: SpatialGrid,
+    /// where every special effect block is, ids are indexes into special_effects.instances
+    pub effect_grid: SpatialGrid,
+    /// how close to the bus a passenger has to be for it to matter, the biggest of NEAR_BUS,
+    /// any zombie's spit_range and reach
+    near_bus: f32,
+    pub special_effects: SpecialEffects,
+    pub bus_stops: Vec<BusStop>,
+    /// on the bus, only on maps with stops as riders need one to get off at
+    pub riders: Vec<Rider>,
+    pub hospitals: Vec<Vector3<f32>>,
+    hospital_timer: f32,
+    pub score: i32,
+    pub lives: i32,
+    /// 0 up to MAX_BUS_DAMAGE from hitting zombies and scenery and being spat at
+    pub bus_damage: i32,
+    pub tick: i128,
+    pub game_over: bool,
+    /// target score reached, the world stops until the next level replaces it
+    pub level_complete: bool,
+    pub level: Level,
+    pub zombie_types: Vec<ZombieType>,
+    /// the level's spawn weight for each of zombie_types
+    zombie_weights: Vec<f32>,
+    /// classic unless the level has a [horde] table
+    pub spawn_policy: Box<dyn SpawnPolicy>,
+    /// where roads cross from one tile to the next, horde zombies arrive here
+    tile_edges: Vec<Vector3<f32>>,
+    /// being played instead of going for the level's target score
+    pub mission: Option<MissionProgress>,
+    pub events: Vec<WorldEvent>,
+    pub rng: GameRng,
+}
+
+impl World {
+    pub fn new(seed: u64, level: &Level) -> World {
+        let bus_start = vec3(level.bus_start[0], level.bus_start[1], level.bus_start[2]);
+        let ground = Ground::new(&level.map);
+        let bus_stops = ground.bus_stops();
+        let hospitals = ground.hospitals();
+        let zombie_types = load_zombie_types(ZOMBIE_TYPES_FILE).unwrap_or_else(|e| panic!("{}", e));
+        for name in level.zombie_weights.keys() {
+            if !zombie_types.iter().any(|z| &z.name == name) {
+                panic!("level {} has a weight for {} but there is no zombie called that", level.name, name);
+            }
+        }
+        let tuning = load_bus_tunings(BUS_TUNINGS_FILE).unwrap_or_else(|e| panic!("{}", e))
+            .into_iter().find(|b| b.name == level.bus)
+            .unwrap_or_else(|| panic!("level {} drives {} but there is no bus called that", level.name, level.bus));
+        let near_bus = zombie_types.iter().fold(NEAR_BUS, |near, z| near.max(z.spit_range).max(z.reach.sqrt()));
+        let passenger_grid = SpatialGrid::new(ground.width(), ground.by * GRID_SQUARES_PER_TILE);
+        let effect_grid = SpatialGrid::new(ground.width(), ground.by * GRID_SQUARES_PER_TILE);
+        let zombie_weights = zombie_types.iter().map(|z| *level.zombie_weights.get(&z.name).unwrap_or(&0.0)).collect();
+        let spawn_policy = spawn_policy_for(level);
+        let tile_edges = tile_edges(&ground);
+        let mut world = World {
+            ground,
+            bus: CarMainPlayer::new(bus_start, level.bus_heading, tuning),
+            passengers: vec![],
+            traffic: vec![],
+            passenger_grid,
+            effect_grid,
+            near_bus,
+            special_effects: SpecialEffects::new(),
+            bus_stops,
+            riders: vec![],
+            hospitals,
+            hospital_timer: 0.0,
+            score: 0,
+            lives: level.lives,
+            bus_damage: 0,
+            tick: 0,
+            game_over: false,
+            level_complete: false,
+            level: level.clone(),
+            zombie_types,
+            zombie_weights,
+            spawn_policy,
+            tile_edges,
+            mission: None,
+            events: vec![],
+            rng: GameRng::seed_from_u64(seed),
+        };
+        world.add_traffic();
+        world
+    }
+
+    /// Back to how World::new left it, keeping the ground, road graph and zombie kinds
+    /// rather than reading them in again.
+    pub fn restart(&mut self, seed: u64) {
+        let bus_start = vec3(self.level.bus_start[0], self.level.bus_start[1], self.level.bus_start[2]);
+        self.bus = CarMainPlayer::new(bus_start, self.level.bus_heading, self.bus.tuning.clone());
+        self.passengers.clear();
+        self.traffic.clear();
+        self.special_effects = SpecialEffects::new();
+        self.bus_stops = self.ground.bus_stops();
+        self.riders.clear();
+        self.hospital_timer = 0.0;
+        self.score = 0;
+        self.lives = self.level.lives;
+        self.bus_damage = 0;
+        self.tick = 0;
+        self.game_over = false;
+        self.level_complete = false;
+        self.spawn_policy = spawn_policy_for(&self.level);
+        self.mission = None;
+        self.events.clear();
+        self.rng = GameRng::seed_from_u64(seed);
+        self.add_traffic();
+        self.index_passengers();
+        self.index_effects();
+    }
+
+    /// A copy of everything that changes, seconds_played is kept by the front end.
+    pub fn save_game(&self, seconds_played: f32) -> SaveGame {
+        SaveGame {
+            version: SAVE_VERSION,
+            level: self.level.clone(),
+            bus: self.bus.clone(),
+            passengers: self.passengers.clone(),
+            traffic: self.traffic.clone(),
+            special_effects: self.special_effects.clone(),
+            bus_stops: self.bus_stops.clone(),
+            riders: self.riders.clone(),
+            hospital_timer: self.hospital_timer,
+            score: self.score,
+            lives: self.lives,
+            bus_damage: self.bus_damage,
+            tick: self.tick,
+            game_over: self.game_over,
+            level_complete: self.level_complete,
+            spawn_policy: self.spawn_policy.save(),
+            mission: self.mission.clone(),
+            rng: self.rng.clone(),
+            seconds_played,
+        }
+    }
+
+    /// The level's world built again with everything from the save put back.
+    pub fn from_save_game(save: SaveGame) -> World {
+        let mut world = World::new(0, &save.level);
+        world.bus = save.bus;
+        world.passengers = save.passengers;
+        world.traffic = save.traffic;
+        world.special_effects = save.special_effects;
+        world.bus_stops = save.bus_stops;
+        world.riders = save.riders;
+        world.hospital_timer = save.hospital_timer;
+        world.score = save.score;
+        world.lives = save.lives;
+        world.bus_damage = save.bus_damage;
+        world.tick = save.tick;
+        world.game_over = save.game_over;
+        world.level_complete = save.level_complete;
+        world.spawn_policy = save.spawn_policy.restore();
+        world.mission = save.mission;
+        world.rng = save.rng;
+        world.index_passengers();
+        world.index_effects();
+        world
+    }
+
+    pub fn humans(&self) -> usize {
+        self.passengers.iter().filter(|p| !p.zombie).count()
+    }
+
+    pub fn step(&mut self, delta: f32, input: &Input) {
+        self.tick += 1;
+        self.events.clear();
+        self.save_previous_positions();
+
+        self.add_some_passengers_if_required(delta);
+
+        self.ground.set_player_position(self.bus.movement_collision.position.x, self.bus.movement_collision.position.z);
+
+        self.special_effects.update(delta, &self.ground, &mut self.rng);
+        self.index_effects();
+        self.effects_hit_bus();
+
+        if self.game_over || self.level_complete || self.mission.as_ref().is_some_and(|m| m.finished()) {
+            return;
+        }
+        if self.score >= self.level.target_score && !self.spawn_policy.endless() && self.mission.is_none() {
+            self.level_complete = true;
+            self.events.push(WorldEvent::LevelComplete);
+            return;
+        }
+        if self.lives <= 0 {
+            self.game_over = true;
+            return;
+        }
+
+        let bus_slow = self.bus.speed.abs() < PICKUP_SPEED;
+        self.update_riders(delta, bus_slow);
+        self.stop_at_stops(delta);
+        self.visit_hospitals(delta, bus_slow);
+        // riders getting on and off have moved everyone along
+        self.index_passengers();
+        let mut near_bus = vec![false; self.passengers.len()];
+        for index in self.passenger_grid.within(self.bus.movement_collision.position, self.near_bus) {
+            near_bus[index] = true;
+        }
+        for index in (0..self.passengers.len()).rev() {
+            let passenger = self.passengers.get_mut(index).unwrap();
+            let outcome = passenger.update(delta, &self.ground, &mut self.special_effects, self.bus.movement_collision.position, self.bus.speed.abs(), near_bus[index], &mut self.rng);
+            if outcome.finished {
+                self.passengers.remove(index);
+            }
+            if outcome.picked_up {
+                self.score += 1;
+                self.events.push(WorldEvent::PassengerPickedUp);
+            }
+            if outcome.spat {
+                self.events.push(WorldEvent::ZombieSpat);
+                self.special_effects.zombie(self.bus.movement_collision.position, &mut self.rng);
+                self.damage_bus(SPIT_DAMAGE);
+            }
+            if outcome.hit_by_bus {
+                self.damage_bus(RUN_OVER_DAMAGE);
+            }
+            if outcome.run_over {
+                self.score += RUN_OVER_SCORE;
+                self.events.push(WorldEvent::ZombieRunOver);
+            }
+            if outcome.exploded_on_bus {
+                self.lives -= 1;
+                self.events.push(WorldEvent::ZombieExploded);
+                let mut over_bus = self.bus.movement_collision.position;
+                over_bus.y += 0.3;
+                self.special_effects.explosion(over_bus, &mut self.rng);
+            }
+        }
+        if self.bus.off_road_too_much() {
+            self.lives -= 1;
+            self.events.push(WorldEvent::OffRoadTooLong);
+        }
+        self.apply_input(input);
+        if let Some(hit) = self.bus.update(delta, &self.ground) {
+            self.damage_bus((hit.impact_speed * CRASH_DAMAGE_PER_SPEED) as i32);
+            self.events.push(WorldEvent::BusHitScenery { scenery_type: hit.scenery_type, impact_speed: hit.impact_speed });
+        }
+        if self.bus.off_road_warning {
+            self.events.push(WorldEvent::OffRoadWarning);
+        }
+        // for the renderer, and ready for next time
+        self.index_passengers();
+        self.index_effects();
+        self.update_traffic(delta);
+        self.track_mission(delta);
+    }
+
+    pub fn start_mission(&mut self, mission: Mission) {
+        if let Some(lives) = mission.lives {
+            self.lives = lives;
+        }
+        self.mission = Some(MissionProgress::new(mission));
+    }
+
+    /// Tells the mission what happened this step, from the events and where the bus is.
+    fn track_mission(&mut self, delta: f32) {
+        let mission = match self.mission.as_mut() {
+            Some(mission) => mission,
+            None => return,
+        };
+        let stops = !self.bus_stops.is_empty();
+        let mut step = MissionStep::default();
+        for event in self.events.iter() {
+            match event {
+                WorldEvent::PassengerDelivered { .. } if stops => step.delivered += 1,
+                WorldEvent::PassengerPickedUp if !stops => step.delivered += 1,
+                WorldEvent::ZombieExploded => step.zombie_explosions += 1,
+                _ => {}
+            }
+        }
+        let bus_position = self.bus.movement_collision.position;
+        step.off_road = self.bus.off_road_warning;
+        step.tile = self.ground.get_current_cell(bus_position.x, bus_position.z);
+        step.lives = self.lives;
+        if mission.update(delta, &step) {
+            match &mission.state {
+                MissionState::FAILED(reason) => self.events.push(WorldEvent::MissionFailed { reason: reason.clone() }),
+                _ => self.events.push(WorldEvent::MissionSucceeded),
+            }
+        }
+    }
+
+    fn index_passengers(&mut self) {
+        let positions = self.passengers.iter().map(|p| p.movement_collision.position);
+        self.passenger_grid.rebuild(positions);
+    }
+
+    fn index_effects(&mut self) {
+        let positions = self.special_effects.instances.iter().map(|e| e.collision.position);
+        self.effect_grid.rebuild(positions);
+    }
+
+    /// Blocks thrown at the bus stop against it, the same as against scenery. Ones from
+    /// something going off on the bus started inside it and carry on out.
+    fn effects_hit_bus(&mut self) {
+        let bus = self.bus.movement_collision.position;
+        let radius = self.bus.movement_collision.radius;
+        for index in self.effect_grid.within(bus, radius) {
+            let effect = &mut self.special_effects.instances[index].collision;
+            let mut was = self.ground.roads.wrapped_delta(bus, effect.previous_position);
+            was.y = 0.0;
+            if was.magnitude() >= radius && effect.position.y < bus.y + BUS_HEIGHT {
+                effect.been_hit = true;
+            }
+        }
+    }
+
+    fn damage_bus(&mut self, damage: i32) {
+        self.bus_damage += damage;
+        if self.bus_damage >= MAX_BUS_DAMAGE {
+            self.bus_damage = 0;
+            self.lives -= 1;
+            self.events.push(WorldEvent::BusWrecked);
+            let mut over_bus = self.bus.movement_collision.position;
+            over_bus.y += 0.3;
+            self.special_effects.explosion(over_bus, &mut self.rng);
+        }
+    }
+
+    fn save_previous_positions(&mut self) {
+        self.bus.movement_collision.previous_position = self.bus.movement_collision.position;
+        for passenger in self.passengers.iter_mut() {
+            passenger.movement_collision.previous_position = passenger.movement_collision.position;
+        }
+        for car in self.traffic.iter_mut() {
+            car.movement_collision.previous_position = car.movement_collision.position;
+        }
+        for effect in self.special_effects.instances.iter_mut() {
+            effect.collision.previous_position = effect.collision.position;
+        }
+    }
+
+    fn apply_input(&mut self, input: &Input) {
+        if input.drive {
+            self.bus.select_reverse(false)
+        } else if input.reverse {
+            self.bus.select_reverse(true)
+        }
+        let steer = if input.left { -1.0 } else if input.right { 1.0 } else { 0.0 };
+        let throttle = if input.throttle { 1.0 } else { 0.0 };
+        let brake = if input.brake { 1.0 } else { 0.0 };
+        self.bus.drive(throttle, brake, steer);
+    }
+
+    /// A rider who starts turning gets thrown off if the bus stops in time, otherwise
+    /// they go for the driver and it costs a life.
+    fn update_riders(&mut self, delta: f32, bus_slow: bool) {
+        for index in (0..self.riders.len()).rev() {
+            let was_turning = self.riders[index].turning();
+            self.riders[index].update(delta);
+            if self.riders[index].turning() && !was_turning {
+                self.events.push(WorldEvent::RiderTurning);
+            }
+            if self.riders[index].turning() && bus_slow {
+                let rider = self.riders.remove(index);
+                self.score -= RIDER_TURNED_PENALTY;
+                self.events.push(WorldEvent::RiderEjected);
+                let mut beside_bus = self.bus.movement_collision.position + vec3(EJECT_DISTANCE, 0.0, 0.0);
+                beside_bus.y = 0.2;
+                let zombie_type = rider.zombie_type_index;
+                let zombie = Passenger::new_zombie(self.ground.roads.wrap_position(beside_bus), zombie_type, &self.zombie_types[zombie_type], &mut self.rng);
+                self.passengers.push(zombie);
+            } else if self.riders[index].turned() {
+                self.riders.remove(index);
+                self.score -= RIDER_TURNED_PENALTY;
+                self.lives -= 1;
+                self.events.push(WorldEvent::RiderTurned);
+                let mut over_bus = self.bus.movement_collision.position;
+                over_bus.y += 0.3;
+                self.special_effects.explosion(over_bus, &mut self.rng);
+            }
+        }
+    }
+
+    /// Waiting outside a hospital cures sick riders one at a time.
+    fn visit_hospitals(&mut self, delta: f32, bus_slow: bool) {
+        let bus_position = self.bus.movement_collision.position;
+        let roads = &self.ground.roads;
+        let at_hospital = self.hospitals.iter().any(|h| {
+            let mut to_bus = roads.wrapped_delta(*h, bus_position);
+            to_bus.y = 0.0;
+            to_bus.magnitude() < HOSPITAL_RADIUS
+        });
+        if !bus_slow || !at_hospital {
+            self.hospital_timer = 0.0;
+            return;
+        }
+        self.hospital_timer += delta;
+        if self.hospital_timer >= CURE_SECONDS {
+            self.hospital_timer = 0.0;
+            if let Some(rider) = self.riders.iter_mut().find(|r| r.sick()) {
+                rider.cured = true;
+                self.events.push(WorldEvent::RiderCured);
+            }
+        }
+    }
+
+    /// While the bus waits at a stop riders for there get off one at a time,
+    /// then the front of the queue gets on if there is room.
+    fn stop_at_stops(&mut self, delta: f32) {
+        let bus_position = self.bus.movement_collision.position;
+        for stop_index in 0..self.bus_stops.len() {
+            if !self.bus_stops[stop_index].ready_to_board(delta, bus_position, self.bus.speed.abs(), &self.ground.roads) {
+                continue;
+            }
+            if let Some(index) = self.riders.iter().position(|r| r.destination == stop_index) {
+                let rider = self.riders.remove(index);
+                self.score += rider.delivery_score();
+                self.events.push(WorldEvent::PassengerDelivered { score: rider.delivery_score() });
+                continue;
+            }
+            if self.riders.len() >= self.level.bus_capacity {
+                continue;
+            }
+            let front = self.passengers.iter().enumerate()
+                .filter(|(_, p)| p.waiting_at == Some(stop_index) && !p.zombie && p.zombie_countdown >= 0.0)
+                .min_by_key(|(_, p)| p.queue_place)
+                .map(|(index, _)| index);
+            if let Some(index) = front {
+                let passenger = self.passengers.remove(index);
+                self.riders.push(Rider::new(passenger.destination.unwrap_or(stop_index), passenger.zombie_countdown, passenger.zombie_type_index));
+                self.events.push(WorldEvent::PassengerPickedUp);
+            }
+        }
+    }
+
+    /// The level's cars, each on a random road away from the bus.
+    fn add_traffic(&mut self) {
+        let bus_position = self.bus.movement_collision.position;
+        let roads = &self.ground.roads;
+        let edges: Vec<(usize, usize)> = roads.edges.iter()
+            .filter(|e| roads.wrapped_delta(bus_position, roads.nodes[e.from].position).magnitude() > TRAFFIC_CLEAR_OF_BUS)
+            .map(|e| (e.from, e.to)).collect();
+        if edges.is_empty() {
+            return;
+        }
+        for _ in 0..self.level.traffic {
+            let (from, to) = edges[self.rng.gen_range(0, edges.len())];
+            let car = TrafficCar::new(&self.ground, from, to);
+            self.traffic.push(car);
+        }
+    }
+
+    /// Cars drive on, stopping for the bus and each other, then anything they hit.
+    fn update_traffic(&mut self, delta: f32) {
+        let bus_position = self.bus.movement_collision.position;
+        let positions: Vec<Vector3<f32>> = self.traffic.iter().map(|c| c.movement_collision.position).collect();
+        for index in 0..self.traffic.len() {
+            let roads = &self.ground.roads;
+            let car = &self.traffic[index];
+            let blocked = car.sees_ahead(roads, bus_position)
+                || positions.iter().enumerate().any(|(other, p)| other != index && car.sees_ahead(roads, *p));
+            self.traffic[index].update(delta, &self.ground, blocked, &mut self.rng);
+        }
+        for index in 0..self.traffic.len() {
+            self.car_meets_bus(index);
+        }
+        self.cars_meet_passengers(delta);
+    }
+
+    /// Pushes the bus off a car, crashing them both if either was going fast enough.
+    fn car_meets_bus(&mut self, index: usize) {
+        let car = &self.traffic[index];
+        let mut apart = self.ground.roads.wrapped_delta(car.movement_collision.position, self.bus.movement_collision.position);
+        apart.y = 0.0;
+        let overlap = CAR_RADIUS + self.bus.movement_collision.radius - apart.magnitude();
+        if overlap <= 0.0 || apart.magnitude() == 0.0 {
+            return;
+        }
+        let push = apart.normalize() * overlap;
+        let rammed = car.speed * car.facing.dot(apart.normalize()).max(0.0);
+        let impact_speed = self.bus.bump(push) + rammed;
+        if impact_speed >= CRASH_SPEED {
+            self.traffic[index].crash();
+            self.damage_bus((impact_speed * CRASH_DAMAGE_PER_SPEED) as i32);
+            self.events.push(WorldEvent::BusHitCar { impact_speed });
+        }
+    }
+
+    /// Humans get out of the way of moving cars or get knocked down, zombies go for them.
+    fn cars_meet_passengers(&mut self, delta: f32) {
+        let mut knocked_down: Vec<usize> = vec![];
+        for car in self.traffic.iter() {
+            if car.speed < CAR_HIT_SPEED {
+                continue;
+            }
+            let car_position = car.movement_collision.position;
+            for index in self.passenger_grid.within(car_position, CAR_NOTICE) {
+                let passenger = &mut self.passengers[index];
+                // queueing at a stop is on the pavement
+                if passenger.waiting_at.is_some() {
+                    continue;
+                }
+                let mut apart = self.ground.roads.wrapped_delta(car_position, passenger.movement_collision.position);
+                apart.y = 0.0;
+                if apart.magnitude() < CAR_RADIUS + passenger.movement_collision.radius {
+                    if passenger.hit_by_car(&self.ground, &mut self.special_effects, car_position, &mut self.rng) && !knocked_down.contains(&index) {
+                        knocked_down.push(index);
+                    }
+                } else if apart.dot(car.facing) > 0.0 {
+                    passenger.react_to_car(delta, &self.ground, car_position, car.facing);
+                }
+            }
+        }
+        if knocked_down.is_empty() {
+            return;
+        }
+        knocked_down.sort();
+        for index in knocked_down.iter().rev() {
+            self.passengers.remove(*index);
+            self.events.push(WorldEvent::HumanHitByCar);
+        }
+        self.index_passengers();
+    }
+
+    /// The spawn policy says what is needed, the same code adds it whatever the game mode.
+    fn add_some_passengers_if_required(&mut self, delta: f32) {
+        let population = Population {
+            humans: self.humans(),
+            zombies: self.passengers.len() - self.humans(),
+            passengers: self.passengers.len(),
+        };
+        let (spawns, event) = self.spawn_policy.spawn(delta, &population, &self.level);
+        for spawn in spawns {
+            match spawn {
+                Spawn::HUMANS => {
+                    if self.bus_stops.is_empty() {
+                        self.add_passengers_on_roads();
+                    } else {
+                        self.add_passengers_at_stops();
+                    }
+                }
+                Spawn::ZOMBIES { count, speed } => self.add_zombies_at_tile_edges(count, speed),
+            }
+        }
+        match event {
+            Some(SpawnEvent::WaveStarted { wave }) => self.events.push(WorldEvent::WaveStarted { wave }),
+            Some(SpawnEvent::WaveCleared { wave }) => self.events.push(WorldEvent::WaveCleared { wave }),
+            None => {}
+        }
+    }
+
+    /// Zombies walking in along the roads from the edges of tiles away from the bus.
+    fn add_zombies_at_tile_edges(&mut self, count: usize, speed: f32) {
+        let bus_position = self.bus.movement_collision.position;
+        let roads = &self.ground.roads;
+        let mut away: Vec<Vector3<f32>> = self.tile_edges.iter().cloned()
+            .filter(|p| roads.wrapped_delta(bus_position, *p).magnitude() > HORDE_CLEAR_OF_BUS).collect();
+        if away.is_empty() {
+            away = self.tile_edges.clone();
+        }
+        for _ in 0..count {
+            let mut start_position = away[self.rng.gen_range(0, away.len())];
+            start_position.y = 0.2;
+            let zombie_type = pick_zombie_type(&self.zombie_weights, &mut self.rng);
+            let mut faster = self.zombie_types[zombie_type].clone();
+            faster.speed *= speed;
+            let zombie = Passenger::new_zombie(start_position, zombie_type, &faster, &mut self.rng);
+            self.passengers.push(zombie);
+        }
+    }
+
+    fn add_passengers_at_stops(&mut self) {
+        for stop_index in 0..self.bus_stops.len() {
+            if self.passengers.len() > self.level.max_passengers || self.rng.gen_range(0.0, 1.0) >= self.level.spawn_chance {
+                continue;
+            }
+            // first free place in the queue, people further back don't shuffle up
+            let taken: Vec<usize> = self.passengers.iter()
+                .filter(|p| p.waiting_at == Some(stop_index))
+                .map(|p| p.queue_place).collect();
+            let place = (0..MAX_QUEUE).find(|place| !taken.contains(place));
+            if let Some(place) = place {
+                let mut start_position = self.bus_stops[stop_index].queue_position(place);
+                start_position.y = 0.2;
+                let zombie_type = pick_zombie_type(&self.zombie_weights, &mut self.rng);
+                let mut passenger = Passenger::new(start_position, zombie_type, &self.zombie_types[zombie_type], &mut self.rng);
+                passenger.set_random_time_to_zombie(&mut self.rng, self.level.zombie_seconds);
+                passenger.waiting_at = Some(stop_index);
+                passenger.queue_place = place;
+                passenger.destination = Some(self.random_destination(stop_index));
+                self.passengers.push(passenger)
+            }
+        }
+    }
+
+    /// Any stop but the one they are waiting at.
+    fn random_destination(&mut self, from: usize) -> usize {
+        if self.bus_stops.len() < 2 {
+            return from;
+        }
+        let destination = self.rng.gen_range(0, self.bus_stops.len() - 1);
+        if destination >= from { destination + 1 } else { destination }
+    }
+
+    /// Maps without any stops get passengers in the middle of random roads instead.
+    fn add_passengers_on_roads(&mut self) {
+        let max_passengers = self.level.max_passengers;
+        for r in self.ground.land.iter() {
+            for l in r.iter() {
+                if self.rng.gen_range(0.0, 1.0) < self.level.spawn_chance {
+                    for landscape_object in l.landscape_objects.iter() {
+                        if landscape_object.description.starts_with("road") && self.passengers.len() <= max_passengers {
+                            let mut start_position = vec3(0.0, 0.0, 0.0);
+                            for v in landscape_object.vertices.iter() {
+                                start_position = start_position.add(v);
+                            }
+                            start_position = start_position.div(landscape_object.vertices.len() as f32);
+                            start_position += l.xyz;
+                            start_position.y = 0.2;
+                            let zombie_type = pick_zombie_type(&self.zombie_weights, &mut self.rng);
+                            let mut passenger = Passenger::new(start_position, zombie_type, &self.zombie_types[zombie_type], &mut self.rng);
+                            passenger.set_random_time_to_zombie(&mut self.rng, self.level.zombie_seconds);
+                            self.passengers.push(passenger)
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn spawn_policy_for(level: &Level) -> Box<dyn SpawnPolicy> {
+    match &level.horde {
+        Some(settings) => Box::new(Horde::new(settings.clone())),
+        None => Box::new(Classic),
+    }
+}
+
+/// Road graph nodes on the line between two tiles, or the middle of every tile on a map
+/// with no roads crossing between them.
+fn tile_edges(ground: &Ground) -> Vec<Vector3<f32>> {
+    let half = ground.width() / 2.0;
+    let on_edge = |v: f32| {
+        let across = (v + half) % Ground::MUL;
+        !(ON_TILE_EDGE..=Ground::MUL - ON_TILE_EDGE).contains(&across)
+    };
+    let edges: Vec<Vector3<f32>> = ground.roads.nodes.iter()
+        .map(|n| n.position)
+        .filter(|p| on_edge(p.x) || on_edge(p.z)).collect();
+    if !edges.is_empty() {
+        return edges;
+    }
+    ground.land.iter().flat_map(|row| row.iter().map(|l| l.xyz)).collect()
+}
+
+/// Index of a zombie type chosen in proportion to the weights.
+fn pick_zombie_type(weights: &[f32], rng: &mut GameRng) -> usize {
+    let total: f32 = weights.iter().sum();
+    let mut pick = rng.gen_range(0.0, total);
+    for (index, weight) in weights.iter().enumerate() {
+        if pick < *weight {
+            return index;
+        }
+        pick -= weight;
+    }
+    weights.iter().rposition(|w| *w > 0.0).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::level::DEFAULT_LEVEL;
+    use crate::mission::{load_missions, MISSIONS_FILE};
+    use crate::passengers::RUN_OVER_SPEED;
+
+    use super::*;
+
+    const STEP: f32 = 1.0 / 40.0;
+
+    fn new_world() -> World {
+        World::new(1234, &Level::load(DEFAULT_LEVEL).unwrap())
+    }
+
+    #[test]
+    fn the_same_seed_and_input_play_the_same_game() {
+        let mut world = new_world();
+        let mut again = new_world();
+        let start = world.bus.movement_collision.position;
+        let input = Input { throttle: true, ..Input::default() };
+        for _ in 0..200 {
+            world.step(STEP, &input);
+            again.step(STEP, &input);
+        }
+        assert_eq!(world.tick, 200);
+        assert!((world.bus.movement_collision.position - start).magnitude() > 0.5);
+        assert_eq!(world.bus.movement_collision.position, again.bus.movement_collision.position);
+        assert_eq!(world.score, again.score);
+        assert_eq!(world.passengers.len(), again.passengers.len());
+    }
+
+    #[test]
+    fn the_bus_answers_the_input_on_the_same_step() {
+        let mut world = new_world();
+        world.step(STEP, &Input { throttle: true, ..Input::default() });
+        assert!(world.bus.speed > 0.0);
+        world.step(STEP, &Input { brake: true, ..Input::default() });
+        world.step(STEP, &Input { brake: true, ..Input::default() });
+        assert_eq!(world.bus.speed, 0.0);
+    }
+
+    #[test]
+    fn a_turning_rider_is_thrown_off_a_slow_bus() {
+        let mut world = new_world();
+        let zombie_type = world.zombie_types.len() - 1;
+        world.riders.push(Rider::new(0, -0.1, zombie_type));
+        let score = world.score;
+        world.step(STEP, &Input::default());
+        assert!(world.riders.is_empty());
+        assert!(world.events.contains(&WorldEvent::RiderEjected));
+        assert_eq!(world.score, score - RIDER_TURNED_PENALTY);
+        assert!(world.passengers.iter().any(|p| p.zombie && p.zombie_type_index == zombie_type));
+    }
+
+    /// A zombie of the named type right where the bus is, the bus going at speed.
+    fn zombie_under_bus(world: &mut World, name: &str, speed: f32) -> usize {
+        // the bus starts in the air
+        while world.tick < 80 {
+            world.step(STEP, &Input::default());
+        }
+        let zombie_type = world.zombie_types.iter().position(|t| t.name == name).unwrap();
+        let mut at = world.bus.movement_collision.position;
+        at.y = 0.2;
+        let zombie = Passenger::new_zombie(at, zombie_type, &world.zombie_types[zombie_type].clone(), &mut world.rng);
+        world.passengers.push(zombie);
+        world.bus.speed = speed;
+        world.passengers.len() - 1
+    }
+
+    #[test]
+    fn a_fast_bus_runs_a_zombie_over() {
+        let mut world = new_world();
+        let (score, lives) = (world.score, world.lives);
+        zombie_under_bus(&mut world, "walker", RUN_OVER_SPEED * 2.0);
+        world.step(STEP, &Input::default());
+        assert!(world.events.contains(&WorldEvent::ZombieRunOver));
+        assert!(!world.events.contains(&WorldEvent::ZombieExploded));
+        assert_eq!(world.score, score + RUN_OVER_SCORE);
+        assert_eq!(world.bus_damage, RUN_OVER_DAMAGE);
+        assert_eq!(world.lives, lives);
+    }
+
+    #[test]
+    fn a_zombie_blows_up_on_a_slow_bus() {
+        let mut world = new_world();
+        let lives = world.lives;
+        zombie_under_bus(&mut world, "walker", 0.0);
+        world.step(STEP, &Input::default());
+        assert!(world.events.contains(&WorldEvent::ZombieExploded));
+        assert!(!world.events.contains(&WorldEvent::ZombieRunOver));
+        assert_eq!(world.bus_damage, 0);
+        assert_eq!(world.lives, lives - 1);
+    }
+
+    #[test]
+    fn driving_over_a_tank_is_one_hit() {
+        let mut world = new_world();
+        let tank = zombie_under_bus(&mut world, "tank", RUN_OVER_SPEED * 2.0);
+        // still under the bus for a few steps, knocked back or not
+        for _ in 0..5 {
+            world.passengers[tank].movement_collision.position = world.bus.movement_collision.position;
+            world.bus.speed = RUN_OVER_SPEED * 2.0;
+            world.step(STEP, &Input::default());
+            assert!(!world.events.contains(&WorldEvent::ZombieRunOver));
+        }
+        assert_eq!(world.bus_damage, RUN_OVER_DAMAGE);
+        assert!(!world.passengers[tank].zombie_exploding);
+
+        // coming back round for it later is the second hit
+        for _ in 0..40 {
+            world.step(STEP, &Input::default());
+        }
+        world.passengers[tank].movement_collision.position = world.bus.movement_collision.position;
+        world.bus.speed = RUN_OVER_SPEED * 2.0;
+        world.step(STEP, &Input::default());
+        assert!(world.events.contains(&WorldEvent::ZombieRunOver));
+        assert_eq!(world.bus_damage, RUN_OVER_DAMAGE * 2);
+    }
+
+    #[test]
+    fn blocks_thrown_at_the_bus_stop_against_it() {
+        let mut world = new_world();
+        // the bus starts in the air
+        for _ in 0..80 {
+            world.step(STEP, &Input::default());
+        }
+        world.special_effects.instances.clear();
+        let bus = world.bus.movement_collision.position;
+        // flying east from just west of the bus, and one from going off on top of it
+        world.special_effects._fire(bus - vec3(0.5, 0.0, 0.0), vec3(10.0, 0.0, 0.0), STEP, 0.0, 0.08);
+        let thrown = world.special_effects.instances[0].id;
+        world.special_effects.explosion(bus + vec3(0.0, 0.3, 0.0), &mut world.rng);
+        let blocks = world.special_effects.instances.len() - 1;
+        for _ in 0..40 {
+            world.step(STEP, &Input::default());
+        }
+        assert!(!world.special_effects.instances.iter().any(|e| e.id == thrown));
+        // all of the explosion but the flash at the start, that only lasts 0.2 seconds
+        assert_eq!(world.special_effects.instances.len(), blocks - 1);
+        assert_eq!(world.effect_grid.within(bus, 100.0).len(), world.special_effects.instances.len());
+    }
+
+    fn zombie_positions(world: &World) -> Vec<Vector3<f32>> {
+        world.passengers.iter().filter(|p| p.zombie).map(|p| p.movement_collision.position).collect()
+    }
+
+    #[test]
+    fn horde_zombies_walk_in_from_tile_edges() {
+        let horde = Level::load("resources/levels/horde.toml").unwrap();
+        let mut world = World::new(1234, &horde);
+        while !world.events.contains(&WorldEvent::WaveStarted { wave: 1 }) {
+            world.step(STEP, &Input::default());
+            assert!(world.tick < 1000, "no wave");
+        }
+        let zombies = zombie_positions(&world);
+        assert_eq!(zombies.len(), horde.horde.as_ref().unwrap().first_wave);
+        let roads = &world.ground.roads;
+        let bus = world.bus.movement_collision.position;
+        for zombie in zombies.iter() {
+            // they have had one step to walk off it
+            assert!(world.tile_edges.iter().any(|e| {
+                let mut apart = roads.wrapped_delta(*zombie, *e);
+                apart.y = 0.0;
+                apart.magnitude() < ON_TILE_EDGE
+            }), "{:?} is not on a tile edge", zombie);
+            assert!(roads.wrapped_delta(bus, *zombie).magnitude() > HORDE_CLEAR_OF_BUS - ON_TILE_EDGE);
+        }
+
+        // the same seed, the same places
+        let mut again = World::new(1234, &horde);
+        while again.tick < world.tick {
+            again.step(STEP, &Input::default());
+        }
+        assert_eq!(zombie_positions(&again), zombies);
+    }
+
+    /// The level 1 world playing a mission from missions.toml.
+    fn on_mission(name: &str) -> World {
+        let mission = load_missions(MISSIONS_FILE).unwrap().into_iter().find(|m| m.name == name).unwrap();
+        let mut world = new_world();
+        world.start_mission(mission);
+        world
+    }
+
+    /// What the mission makes of these events happening over that many seconds.
+    fn mission_after(world: &mut World, seconds: f32, events: Vec<WorldEvent>) -> MissionState {
+        world.events = events;
+        world.track_mission(seconds);
+        world.mission.as_ref().unwrap().state.clone()
+    }
+
+    fn delivered() -> WorldEvent {
+        WorldEvent::PassengerDelivered { score: 1 }
+    }
+
+    #[test]
+    fn deliver_enough_in_time() {
+        let mut world = on_mission("rush");
+        for _ in 0..9 {
+            assert_eq!(mission_after(&mut world, 10.0, vec![delivered(), WorldEvent::PassengerPickedUp]), MissionState::RUNNING);
+        }
+        assert_eq!(mission_after(&mut world, 10.0, vec![delivered()]), MissionState::SUCCEEDED);
+        assert_eq!(world.events.last(), Some(&WorldEvent::MissionSucceeded));
+        assert!(world.mission.as_ref().unwrap().progress().starts_with("delivered 10/10"));
+        // and that's it, nothing after counts
+        assert_eq!(mission_after(&mut world, 500.0, vec![]), MissionState::SUCCEEDED);
+    }
+
+    #[test]
+    fn deliver_too_few_in_time() {
+        let mut world = on_mission("rush");
+        assert_eq!(mission_after(&mut world, 100.0, vec![delivered(), delivered()]), MissionState::RUNNING);
+        assert_eq!(mission_after(&mut world, 79.0, vec![delivered()]), MissionState::RUNNING);
+        let failed = MissionState::FAILED(String::from("out of time"));
+        assert_eq!(mission_after(&mut world, 1.0, vec![]), failed);
+        assert_eq!(world.events.last(), Some(&WorldEvent::MissionFailed { reason: String::from("out of time") }));
+        // a step after the mission is over doesn't move anyone
+        world.step(STEP, &Input::default());
+        assert_eq!(world.mission.as_ref().unwrap().count, 3);
+        assert!(world.passengers.iter().all(|p| p.movement_collision.position == p.movement_collision.previous_position));
+    }
+
+    #[test]
+    fn reach_the_tile_on_the_road() {
+        let mut world = on_mission("cross town");
+        assert_eq!(mission_after(&mut world, 60.0, vec![]), MissionState::RUNNING);
+        // the middle of tile 4_4
+        world.bus.movement_collision.position = world.ground.land[4][4].xyz;
+        assert_eq!(mission_after(&mut world, 60.0, vec![]), MissionState::SUCCEEDED);
+    }
+
+    #[test]
+    fn reach_the_tile_but_going_off_road_on_the_way() {
+        let mut world = on_mission("cross town");
+        world.bus.off_road_warning = true;
+        assert_eq!(mission_after(&mut world, 1.0, vec![WorldEvent::OffRoadWarning]), MissionState::FAILED(String::from("went off the road")));
+        world.bus.movement_collision.position = world.ground.land[4][4].xyz;
+        world.bus.off_road_warning = false;
+        assert_eq!(mission_after(&mut world, 1.0, vec![]), MissionState::FAILED(String::from("went off the road")));
+    }
+
+    #[test]
+    fn survive_the_explosions() {
+        let mut world = on_mission("hold on");
+        assert_eq!(world.lives, 6);
+        for _ in 0..4 {
+            world.lives -= 1;
+            assert_eq!(mission_after(&mut world, 30.0, vec![WorldEvent::ZombieExploded]), MissionState::RUNNING);
+        }
+        world.lives -= 1;
+        assert_eq!(mission_after(&mut world, 30.0, vec![WorldEvent::ZombieExploded, WorldEvent::ZombieRunOver]), MissionState::SUCCEEDED);
+        assert_eq!(world.lives, 1);
+    }
+
+    #[test]
+    fn the_last_explosion_takes_the_last_life() {
+        let mut world = on_mission("hold on");
+        world.lives = 4;
+        for _ in 0..4 {
+            world.lives -= 1;
+            mission_after(&mut world, 30.0, vec![WorldEvent::ZombieExploded]);
+        }
+        assert_eq!(world.lives, 0);
+        assert_eq!(world.mission.as_ref().unwrap().state, MissionState::FAILED(String::from("out of lives")));
+        world.lives = 1;
+        assert_eq!(mission_after(&mut world, 30.0, vec![WorldEvent::ZombieExploded]), MissionState::FAILED(String::from("out of lives")));
+    }
+
+    /// The first car going at speed and someone distance in front of it.
+    fn in_front_of_a_car(world: &mut World, distance: f32, zombie: Option<&str>) -> usize {
+        let car = &mut world.traffic[0];
+        car.speed = CAR_HIT_SPEED * 2.0;
+        let mut at = car.movement_collision.position + car.facing * distance;
+        at.y = 0.2;
+        let passenger = match zombie {
+            Some(name) => {
+                let zombie_type = world.zombie_types.iter().position(|t| t.name == name).unwrap();
+                Passenger::new_zombie(at, zombie_type, &world.zombie_types[zombie_type].clone(), &mut world.rng)
+            }
+            None => Passenger::new(at, 0, &world.zombie_types[0].clone(), &mut world.rng),
+        };
+        world.passengers.push(passenger);
+        world.index_passengers();
+        world.passengers.len() - 1
+    }
+
+    #[test]
+    fn a_car_knocks_a_human_down() {
+        let mut world = new_world();
+        let human = in_front_of_a_car(&mut world, 0.1, None);
+        let at = world.passengers[human].movement_collision.position;
+        world.step(STEP, &Input::default());
+        assert!(world.events.contains(&WorldEvent::HumanHitByCar));
+        assert!(!world.passengers.iter().any(|p| !p.zombie && (p.movement_collision.position - at).magnitude() < 0.1));
+    }
+
+    #[test]
+    fn a_car_is_a_hit_for_a_zombie() {
+        let mut world = new_world();
+        let walker = in_front_of_a_car(&mut world, 0.1, Some("walker"));
+        world.step(STEP, &Input::default());
+        assert!(world.passengers[walker].zombie_exploding);
+        assert!(!world.events.contains(&WorldEvent::HumanHitByCar));
+
+        let mut world = new_world();
+        let tank = in_front_of_a_car(&mut world, 0.1, Some("tank"));
+        world.step(STEP, &Input::default());
+        assert!(!world.passengers[tank].zombie_exploding);
+        // knocked out of the way
+        let car = world.traffic[0].movement_collision.position;
+        assert!(world.ground.roads.wrapped_delta(car, world.passengers[tank].movement_collision.position).magnitude() > 0.4);
+    }
+
+    #[test]
+    fn humans_step_out_of_the_way_of_a_car() {
+        let mut world = new_world();
+        let human = in_front_of_a_car(&mut world, 0.7, None);
+        let car = world.traffic[0].movement_collision.position;
+        let facing = world.traffic[0].facing;
+        let sideways = |p: Vector3<f32>| {
+            let mut apart = p - car;
+            apart.y = 0.0;
+            (apart - facing * apart.dot(facing)).magnitude()
+        };
+        let was = world.passengers[human].movement_collision.position;
+        for _ in 0..10 {
+            world.passengers[human].react_to_car(STEP, &world.ground, car, facing);
+        }
+        let now = world.passengers[human].movement_collision.position;
+        assert!((sideways(now) - sideways(was) - 10.0 * STEP).abs() < 0.001);
+        assert!(((now - was).dot(facing)).abs() < 0.001);
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::mem;
+use std::os::raw::c_void;
+use std::ptr;
+
+use cgmath::{Matrix4, vec2, vec3, Vector2, Vector3};
+
+use crate::{get_start_time, gl, output_elapsed};
+use crate::car_main_player;
+use crate::cube::Cube;
+use crate::gl_helper::gl_matrix4;
+use crate::gl_helper::instance_model::ModelInstance;
+use crate::gl_helper::model::Model;
+use crate::gl_helper::texture::create_texture_png;
+use crate::ground::Ground;
+use crate::passengers::{Passenger, PASSENGER_SCALE};
+use crate::scenery::{Scenery, SceneryType};
+use crate::special_effects::{EffectColour, SpecialInstance};
+use crate::traffic::{CAR_SCALE, TrafficCar};
+use crate::world::World;
+
+struct LandscapeMesh {
+    vao: u32,
+    vertices_count: usize,
+}
+
+/// Owns every OpenGL resource needed to draw a World, the World itself never touches GL.
+pub struct WorldRenderer {
+    ground_texture: u32,
+    land: Vec<Vec<LandscapeMesh>>,
+    to_display: HashMap<Vector2<i32>, Vector2<i32>>,
+    tree: ModelInstance,
+    house: ModelInstance,
+    office1: ModelInstance,
+    hospital: ModelInstance,
+    passenger_instances: Vec<ModelInstance>,
+    /// the same animation frames again for each zombie type, with its own texture
+    zombie_instances: Vec<Vec<ModelInstance>>,
+    bus_instances: Vec<ModelInstance>,
+    car: ModelInstance,
+    cube: Cube,
+    yellow: u32,
+    purple: u32,
+}
+
+impl WorldRenderer {
+    pub fn new(gl: &gl::Gl, world: &World) -> WorldRenderer {
+        let start = get_start_time();
+
+        let ground_texture = create_texture_png(gl, "resources/ground.png");
+        let land = WorldRenderer::create_land(gl, world);
+
+        let tree = ModelInstance::new(gl, Model::new(gl, "resources/models/tree.obj", "resources/models/tree.png"), 0.01, None);
+        let house = ModelInstance::new(gl, Model::new(gl, "resources/models/house.obj", "resources/models/house.png"), 0.01, None);
+        let office1_model = Model::new(gl, "resources/models/office1.obj", "resources/models/office1.png");
+        let office1 = ModelInstance::new(gl, office1_model.clone(), 0.09, None);
+        let hospital = ModelInstance::new(gl, office1_model, 0.09, Some("resources/models/hospital.png"));
+
+        let mut bus_instances: Vec<ModelInstance> = vec![];
+        let model = Model::new(gl, "resources/models/bus.obj", "resources/models/bus.png");
+        bus_instances.push(ModelInstance::new(gl, model.clone(), car_main_player::SCALE, Some("resources/models/bus1.png")));
+        bus_instances.push(ModelInstance::new(gl, model.clone(), car_main_player::SCALE, Some("resources/models/bus2.png")));
+        bus_instances.push(ModelInstance::new(gl, model.clone(), car_main_player::SCALE, Some("resources/models/bus3.png")));
+        bus_instances.push(ModelInstance::new(gl, model.clone(), car_main_player::SCALE, Some("resources/models/bus4.png")));
+
+        let car = ModelInstance::new(gl, Model::new(gl, "resources/models/car.obj", "resources/models/car.png"), CAR_SCALE, None);
+
+        let passenger_models = WorldRenderer::load_passenger_models(gl);
+        let zombie_instances = world.zombie_types.iter()
+            .map(|zombie_type| WorldRenderer::create_passenger(gl, &passenger_models, &zombie_type.texture))
+            .collect();
+
+        let cube = Cube::new(gl, "resources/fire.png", vec3(0.001, 0.001, 0.001), 1.0);
+        let yellow = create_texture_png(gl, "resources/yellow.png");
+        let purple = create_texture_png(gl, "resources/purple.png");
+
+        output_elapsed(start, "Time elapsed in world renderer new()");
+        WorldRenderer {
+            ground_texture,
+            land,
+            to_display: HashMap::new(),
+            tree,
+            house,
+            office1,
+            hospital,
+            passenger_instances: WorldRenderer::create_passenger(gl, &passenger_models, "resources/models/zombie.png"),
+            zombie_instances,
+            bus_instances,
+            car,
+            cube,
+            yellow,
+            purple,
+        }
+    }
+
+    /// A new level can bring a different city, everything else stays loaded.
+    pub fn load_landscape(&mut self, gl: &gl::Gl, world: &World) {
+        self.land = WorldRenderer::create_land(gl, world);
+        self.to_display.clear();
+    }
+
+    fn create_land(gl: &gl::Gl, world: &World) -> Vec<Vec<LandscapeMesh>> {
+        let mut land: Vec<Vec<LandscapeMesh>> = vec![];
+        for row in world.ground.land.iter() {
+            let mut meshes: Vec<LandscapeMesh> = vec![];
+            for landscape in row.iter() {
+                let vao = unsafe { WorldRenderer::bind_vertices(gl, &landscape.vertices) };
+                meshes.push(LandscapeMesh { vao, vertices_count: landscape.vertices_count });
+            }
+            land.push(meshes);
+        }
+        land
+    }
+
+    unsafe fn bind_vertices(gl: &gl::Gl, vertices: &[f32]) -> u32 {
+        let (mut vbo, mut vao) = (0, 0);
+        if !vertices.is_empty() {
+            gl.GenVertexArrays(1, &mut vao);
+            gl.GenBuffers(1, &mut vbo);
+
+            gl.BindVertexArray(vao);
+
+            gl.BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl.BufferData(gl::ARRAY_BUFFER,
+                          mem::size_of_val(vertices) as gl::types::GLsizeiptr,
+                          &vertices[0] as *const f32 as *const c_void,
+                          gl::STATIC_DRAW);
+
+            let stride = 5 * mem::size_of::<gl::types::GLfloat>() as gl::types::GLsizei;
+            gl.VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl.EnableVertexAttribArray(0);
+            gl.VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, stride, (3 * mem::size_of::<gl::types::GLfloat>()) as *const c_void);
+            gl.EnableVertexAttribArray(1);
+        }
+        vao
+    }
+
+    /// The animation frames, man0 between each of the others.
+    fn load_passenger_models(gl: &gl::Gl) -> Vec<Model> {
+        let model_zero = Model::new(gl, "resources/models/man0.obj", "resources/models/body.png");
+        let mut models = Vec::<Model>::new();
+        for i in 1..3 {
+            let name = format!("resources/models/man{}.obj", i);
+            println!("Load {}", name);
+            let model = Model::new(gl, name.as_str(), "resources/models/body.png");
+            models.push(model_zero.clone());
+            models.push(model);
+        }
+        models
+    }
+
+    fn create_passenger(gl: &gl::Gl, models: &[Model], zombie_texture: &str) -> Vec<ModelInstance> {
+        let model_zero_instance = ModelInstance::new(gl, models[0].clone(), PASSENGER_SCALE, Some(zombie_texture));
+        let mut instances = Vec::<ModelInstance>::new();
+        for (i, model) in models.iter().enumerate() {
+            if i % 2 == 0 {
+                instances.push(model_zero_instance.clone());
+            } else {
+                instances.push(ModelInstance::new(gl, model.clone(), PASSENGER_SCALE, Some(zombie_texture)));
+            }
+        }
+        instances
+    }
+
+    pub fn update(&mut self, ground: &Ground, player_position: Vector3<f32>, camera_angle: f32) {
+        self.sort_out_what_to_display(ground, player_position, camera_angle);
+    }
+
+    fn sort_out_what_to_display(&mut self, ground: &Ground, player_position: Vector3<f32>, camera_angle: f32) {
+        self.to_display.clear();
+
+        let (current_xx, current_zz) = ground.get_current_cell(player_position.x, player_position.z);
+
+        let xx = current_xx as f32;
+        let zz = current_zz as f32;
+
+        let v = vec2(xx as i32, zz as i32);
+        self.to_display.insert(v, v);
+
+        self.make_sure_everything_around_player_shown(xx, zz);
+
+        // -1 so make sure when low we still show cell
+        for going_away in -1..(ground.by as i32 * 2) {
+            for a in (-120..130).step_by(5) {
+                let apply = Vector3 {
+                    x: (a as f32 - camera_angle).to_radians().sin() * going_away as f32,
+                    y: 0.0,
+                    z: (a as f32 - camera_angle).to_radians().cos() * -going_away as f32,
+                };
+                let v = vec2((xx + apply.x) as i32, (zz + apply.z) as i32);
+                self.to_display.insert(v, v);
+            }
+        }
+    }
+
+    fn make_sure_everything_around_player_shown(&mut self, xx: f32, zz: f32) {
+        let v = vec2(xx as i32 + 1, zz as i32);
+        self.to_display.insert(v, v);
+        let v = vec2(xx as i32 + 1, zz as i32 + 1);
+        self.to_display.insert(v, v);
+        let v = vec2(xx as i32, zz as i32 + 1);
+        self.to_display.insert(v, v);
+        let v = vec2(xx as i32 - 1, zz as i32);
+        self.to_display.insert(v, v);
+        let v = vec2(xx as i32, zz as i32 - 1);
+        self.to_display.insert(v, v);
+        let v = vec2(xx as i32 - 1, zz as i32 - 1);
+        self.to_display.insert(v, v);
+    }
+
+    /// alpha is how far we are between the previous simulation step and the current one.
+    pub fn render(&mut self, gl: &gl::Gl, world: &World, alpha: f32, view: &Matrix4<f32>, projection: &Matrix4<f32>, our_shader: u32) {
+        self.render_ground(gl, world, alpha, view, projection, our_shader);
+        self.render_bus(gl, world, alpha, view, projection, our_shader);
+    }
+
+    fn render_ground(&mut self, gl: &gl::Gl, world: &World, alpha: f32, view: &Matrix4<f32>, projection: &Matrix4<f32>, our_shader: u32) {
+        let to_display: Vec<Vector2<i32>> = self.to_display.values().cloned().collect();
+        for xz in to_display.iter() {
+            let yyy = world.ground.wrap_value(xz.y);
+            let xxx = world.ground.wrap_value(xz.x);
+            let offset = world.ground.width() / 2.0 - Ground::MUL / 2.0;
+            let position = vec3(xz.x as f32 * (Ground::MUL) - offset, 0.0, xz.y as f32 * (Ground::MUL) - offset);
+            let here = Matrix4::<f32>::from_translation(position);
+
+            let landscape = &world.ground.land[yyy as usize][xxx as usize];
+            for scenery in landscape.scenery_instances.iter() {
+                self.render_scenery(gl, view, projection, our_shader, scenery, position);
+            }
+            let mesh = &self.land[yyy as usize][xxx as usize];
+            unsafe {
+                gl.ActiveTexture(gl::TEXTURE0);
+                gl.BindTexture(gl::TEXTURE_2D, self.ground_texture);
+                gl.BindVertexArray(mesh.vao);
+
+                gl_matrix4(gl, our_shader, here, "model");
+                gl_matrix4(gl, our_shader, *view, "view");
+                gl_matrix4(gl, our_shader, *projection, "projection");
+                gl.DrawArrays(gl::TRIANGLES, 0, mesh.vertices_count as i32);
+            }
+
+            let avatar_offset = position - landscape.xyz;
+            let tile_min = landscape.xyz - vec3(Ground::MUL / 2.0, 0.0, Ground::MUL / 2.0);
+            let tile_max = landscape.xyz + vec3(Ground::MUL / 2.0, 0.0, Ground::MUL / 2.0);
+            for index in world.passenger_grid.inside(tile_min, tile_max) {
+                self.render_passenger(gl, view, projection, our_shader, &world.passengers[index], alpha, avatar_offset);
+            }
+            for index in world.effect_grid.inside(tile_min, tile_max) {
+                self.render_special_effect(gl, view, projection, our_shader, &world.special_effects.instances[index], alpha, avatar_offset);
+            }
+            for car in world.traffic.iter() {
+                let p = car.movement_collision.position;
+                if p.x >= tile_min.x && p.x < tile_max.x && p.z >= tile_min.z && p.z < tile_max.z {
+                    self.render_car(gl, view, projection, our_shader, car, alpha, avatar_offset);
+                }
+            }
+        }
+    }
+
+    fn render_scenery(&mut self, gl: &gl::Gl, view: &Matrix4<f32>, projection: &Matrix4<f32>, our_shader: u32, scenery: &Scenery, wrapped_position: Vector3<f32>) {
+        let model_instance = match scenery.scenery_type {
+            SceneryType::TREE => &mut self.tree,
+            SceneryType::HOUSE => &mut self.house,
+            SceneryType::OFFICE1 => &mut self.office1,
+            SceneryType::HOSPITAL => &mut self.hospital,
+        };
+        model_instance.matrix = scenery.matrix * Matrix4::from_translation(wrapped_position);
+        model_instance.render(gl, view, projection, our_shader, scenery.scenery_type == SceneryType::HOSPITAL);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_passenger(&mut self, gl: &gl::Gl, view: &Matrix4<f32>, projection: &Matrix4<f32>, our_shader: u32, passenger: &Passenger, alpha: f32, offset: Vector3<f32>) {
+        let model_instance = if passenger.zombie {
+            &mut self.zombie_instances[passenger.zombie_type_index][passenger.animate as usize]
+        } else {
+            &mut self.passenger_instances[passenger.animate as usize]
+        };
+        let position = passenger.movement_collision.interpolated_position(alpha);
+        model_instance.matrix = Matrix4::from_translation(position + offset) * passenger.applied_rotation;
+        if passenger.zombie {
+            model_instance.scale = passenger.zombie_type.scale;
+            model_instance.render(gl, view, projection, our_shader, true);
+        } else {
+            model_instance.scale = PASSENGER_SCALE;
+            model_instance.render(gl, view, projection, our_shader, false);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_car(&mut self, gl: &gl::Gl, view: &Matrix4<f32>, projection: &Matrix4<f32>, our_shader: u32, car: &TrafficCar, alpha: f32, offset: Vector3<f32>) {
+        let position = car.movement_collision.interpolated_position(alpha);
+        self.car.matrix = car.matrix(position + offset);
+        self.car.render(gl, view, projection, our_shader, false);
+    }
+
+    fn render_bus(&mut self, gl: &gl::Gl, world: &World, alpha: f32, view: &Matrix4<f32>, projection: &Matrix4<f32>, our_shader: u32) {
+        let bus = &world.bus;
+        let alt = bus.tick % 2.0 < 1.25;
+        let instance = (bus.tick % self.bus_instances.len() as f32) as usize;
+        let position = bus.movement_collision.interpolated_position(alpha);
+        let mut matrix = bus.matrix;
+        matrix.w.x = position.x;
+        matrix.w.y = position.y;
+        matrix.w.z = position.z;
+        self.bus_instances[instance].matrix = matrix;
+        self.bus_instances[instance].render(gl, view, projection, our_shader, alt);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_special_effect(&mut self, gl: &gl::Gl, view: &Matrix4<f32>, projection: &Matrix4<f32>, our_shader: u32, effect: &SpecialInstance, alpha: f32, offset: Vector3<f32>) {
+        let scale = Matrix4::<f32>::from_scale(effect.scale);
+        let matrix = Matrix4::<f32>::from_translation(effect.collision.interpolated_position(alpha) + offset) * scale;
+
+        let texture = match effect.textures[effect.tex_index % effect.textures.len()] {
+            EffectColour::FIRE => self.cube.texture,
+            EffectColour::YELLOW => self.yellow,
+            EffectColour::PURPLE => self.purple,
+        };
+        self.cube.render(gl, &matrix, view, projection, our_shader, texture);
+    }
+}
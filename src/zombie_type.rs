@@ -0,0 +1,51 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+pub const ZOMBIE_TYPES_FILE: &str = "resources/zombies.toml";
+
+/// How one kind of zombie moves and fights, one [[zombie]] table in zombies.toml.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ZombieType {
+    /// what levels use to give the spawn weights
+    pub name: String,
+    pub texture: String,
+    pub scale: f32,
+    pub speed: f32,
+    /// world units, further from the bus than this they wander instead of hunting it
+    pub sight: f32,
+    /// squared distance from the bus that counts as reaching it
+    pub reach: f32,
+    /// times it has to reach the bus before it blows up
+    pub hits: i32,
+    /// 0.0 for zombies that don't spit, otherwise how far away they can spit at the bus from
+    pub spit_range: f32,
+    /// how long in range before the spit lands
+    pub spit_seconds: f32,
+    /// goes straight for the bus over anything instead of keeping to the roads
+    pub leaves_road: bool,
+    /// how many blocks fly out when it blows up
+    pub explosion: usize,
+}
+
+#[derive(Deserialize)]
+struct ZombieTypesFile {
+    zombie: Vec<ZombieType>,
+}
+
+pub fn load_zombie_types(filename: &str) -> Result<Vec<ZombieType>, String> {
+    let contents = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let file: ZombieTypesFile = toml::from_str(&contents).map_err(|e| format!("{}: {}", filename, e))?;
+    if file.zombie.is_empty() {
+        return Err(format!("{}: no zombies", filename));
+    }
+    for zombie in file.zombie.iter() {
+        if zombie.hits <= 0 {
+            return Err(format!("{}: {} should take at least 1 hit", filename, zombie.name));
+        }
+        if file.zombie.iter().filter(|z| z.name == zombie.name).count() > 1 {
+            return Err(format!("{}: {} is there more than once", filename, zombie.name));
+        }
+    }
+    Ok(file.zombie)
+}